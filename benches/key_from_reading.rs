@@ -0,0 +1,98 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Compares [`KeyMap::key_from_reading`] with a classification that checks every band, without the idle fast path,
+//! and with a branchless one that looks up the key from which bands match.
+//!
+//! The branchless classification was slower for every reading on a desktop, including the idle-heavy mix,
+//! so it isn't part of the library. It's kept here to re-check on other targets.
+//!
+//! Run with `cargo bench`. Timings on a desktop are only indicative for an embedded target,
+//! but the ratio between idle and pressed readings should carry over.
+//! Needs Rust 1.66 for `black_box`, unlike the library itself.
+#![allow(clippy::incompatible_msrv)]
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use kc11b04::{Key, KeyMap, MAP_10BIT};
+
+const ITERATIONS: u32 = 10_000_000;
+
+/// Checks every band in order, like `key_from_reading` did before the fast path.
+fn without_fast_path(map: &KeyMap<u16>, val: u16) -> Option<Key> {
+	Key::all().into_iter().find(|&key| {
+		let expected = map.expected(key);
+		if val >= expected {
+			key == Key::K4 || val - expected <= map.margin_high
+		} else {
+			expected - val <= map.margin
+		}
+	})
+}
+
+/// Checks every band without stopping at a match, and looks up the lowest matching key.
+fn branchless(map: &KeyMap<u16>, val: u16) -> Option<Key> {
+	const LOWEST: [Option<Key>; 16] = {
+		let (k1, k2, k3, k4) = (Some(Key::K1), Some(Key::K2), Some(Key::K3), Some(Key::K4));
+		[
+			None, k1, k2, k1, k3, k1, k2, k1, k4, k1, k2, k1, k3, k1, k2, k1,
+		]
+	};
+	let in_band = |expected: u16| {
+		(val <= expected.saturating_add(map.margin_high))
+			& (val >= expected.saturating_sub(map.margin))
+	};
+	let mask = in_band(map.k1) as usize
+		| (in_band(map.k2) as usize) << 1
+		| (in_band(map.k3) as usize) << 2
+		| (((val >= map.k4) | in_band(map.k4)) as usize) << 3;
+	LOWEST[mask]
+}
+
+fn bench(name: &str, f: impl Fn(u16) -> Option<Key>, val: u16) -> Duration {
+	let start = Instant::now();
+	for _ in 0..ITERATIONS {
+		black_box(f(black_box(val)));
+	}
+	let elapsed = start.elapsed();
+	println!(
+		"{name:<24} reading {val:>4}: {:>6.2} ns/iter",
+		elapsed.as_nanos() as f64 / ITERATIONS as f64
+	);
+	elapsed
+}
+
+/// Like [`bench`], but cycles through `readings`.
+fn bench_mix(name: &str, f: impl Fn(u16) -> Option<Key>, readings: &[u16]) -> Duration {
+	let start = Instant::now();
+	for i in 0..ITERATIONS as usize {
+		black_box(f(black_box(readings[i % readings.len()])));
+	}
+	let elapsed = start.elapsed();
+	println!(
+		"{name:<24} mixed readings: {:>6.2} ns/iter",
+		elapsed.as_nanos() as f64 / ITERATIONS as f64
+	);
+	elapsed
+}
+
+fn main() {
+	let map = MAP_10BIT;
+	for val in 0..=u16::MAX {
+		assert_eq!(map.key_from_reading(val), branchless(&map, val));
+	}
+	for val in [0, 404, 1023] {
+		assert_eq!(map.key_from_reading(val), without_fast_path(&map, val));
+		bench("with fast path", |v| map.key_from_reading(v), val);
+		bench("without fast path", |v| without_fast_path(&map, v), val);
+		bench("branchless", |v| branchless(&map, v), val);
+	}
+
+	// Mostly idle with some noise, and the occasional key press.
+	let mut readings = [0, 3, 1, 0, 7, 0, 2, 0, 0, 5, 0, 1].repeat(8);
+	readings.extend([404, 607, 812, 1023, 500, 300]);
+	bench_mix("with fast path", |v| map.key_from_reading(v), &readings);
+	bench_mix("branchless", |v| branchless(&map, v), &readings);
+}
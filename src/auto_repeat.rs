@@ -0,0 +1,152 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use embedded_hal::adc::{Channel, OneShot};
+
+use crate::{driver::NbError, Classifier, Key, KeyMap, KC11B04};
+
+/// Wraps a [`KC11B04`] driver to repeatedly report a key while it's held. Constructed with [`AutoRepeat::new`].
+///
+/// Like [`LongPress`][crate::LongPress], every poll takes the current time in ticks. The tick counter may wrap around.
+pub struct AutoRepeat<Pin, ADC, Word, Map = KeyMap<Word>> {
+	keypad: KC11B04<Pin, ADC, Word, Map>,
+	delay: u32,
+	interval: u32,
+	pressed: Option<Repeating>,
+}
+
+/// A key being pressed or repeated, as reported by [`AutoRepeat::poll`].
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyRepeat {
+	/// The key was just pressed.
+	Pressed(Key),
+	/// The key is still being held.
+	Repeat(Key),
+}
+
+struct Repeating {
+	key: Key,
+	since: u32,
+	next: u32,
+}
+
+impl<Pin, ADC, Word, Map> AutoRepeat<Pin, ADC, Word, Map>
+where
+	Pin: Channel<ADC>,
+	Word: Copy,
+	Map: Classifier<Word>,
+{
+	/// Wrap a [`KC11B04`] driver, repeating a held key after `delay` ticks, and then every `interval` ticks.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{AutoRepeat, Key, KC11B04, KeyRepeat, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 1023),
+	/// # ]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// // Start repeating after 500 ticks, then every 100 ticks.
+	/// let mut keypad = AutoRepeat::new(KC11B04::new(analog_pin, MAP_10BIT), 500, 100);
+	///
+	/// assert_eq!(keypad.poll(&mut adc, 0), Ok(Some(KeyRepeat::Pressed(Key::K4))));
+	/// assert_eq!(keypad.poll(&mut adc, 200), Ok(None));
+	/// assert_eq!(keypad.poll(&mut adc, 500), Ok(Some(KeyRepeat::Repeat(Key::K4))));
+	/// ```
+	pub fn new(keypad: KC11B04<Pin, ADC, Word, Map>, delay: u32, interval: u32) -> Self {
+		Self {
+			keypad,
+			delay,
+			interval,
+			pressed: None,
+		}
+	}
+
+	/// Takes an ADC reading at time `now` in ticks, and reports when a key is pressed or should repeat.
+	///
+	/// At most one [`KeyRepeat::Repeat`] is reported per poll.
+	/// When polling less often than `interval`, the missed repeats are skipped.
+	pub fn poll<Adc>(
+		&mut self,
+		adc: &mut Adc,
+		now: u32,
+	) -> Result<Option<KeyRepeat>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let key = self.keypad.key_state(adc)?;
+		let repeat = match &mut self.pressed {
+			Some(held) if Some(held.key) == key => {
+				let elapsed = now.wrapping_sub(held.since);
+				if elapsed >= held.next {
+					held.next = held.next.saturating_add(self.interval);
+					if held.next <= elapsed {
+						held.next = elapsed.saturating_add(self.interval);
+					}
+					Some(KeyRepeat::Repeat(held.key))
+				} else {
+					None
+				}
+			}
+			pressed => {
+				*pressed = key.map(|key| Repeating {
+					key,
+					since: now,
+					next: self.delay,
+				});
+				key.map(KeyRepeat::Pressed)
+			}
+		};
+		Ok(repeat)
+	}
+
+	/// Releases the wrapped [`KC11B04`] driver.
+	pub fn into_inner(self) -> KC11B04<Pin, ADC, Word, Map> {
+		self.keypad
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{AutoRepeat, Key, KeyRepeat, KC11B04, MAP_10BIT};
+	use embedded_hal::adc::Channel;
+	use embedded_hal_mock::adc::{Mock, MockChan0, Transaction};
+
+	#[test]
+	fn repeats_while_held() {
+		use MockChan0 as PIN;
+
+		// Hold K2 for 500 ticks, polling every 25 ticks.
+		let mut polls = [(600, 0); 22];
+		for (i, poll) in polls.iter_mut().enumerate() {
+			poll.1 = i as u32 * 25;
+		}
+		polls[21].0 = 0;
+
+		let expected = polls.map(|(r, _)| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = AutoRepeat::new(KC11B04::new(PIN, MAP_10BIT), 100, 50);
+
+		let repeats = polls.map(|(_, now)| keypad.poll(&mut adc, now).unwrap());
+
+		let pressed = Some(KeyRepeat::Pressed(Key::K2));
+		let repeat = Some(KeyRepeat::Repeat(Key::K2));
+		assert_eq!(
+			repeats,
+			[
+				pressed, None, None, None, // 0 - 75
+				repeat, None, repeat, None, // 100 - 175
+				repeat, None, repeat, None, // 200 - 275
+				repeat, None, repeat, None, // 300 - 375
+				repeat, None, repeat, None, // 400 - 475
+				repeat, None, // 500, released at 525
+			]
+		);
+	}
+}
@@ -0,0 +1,162 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use core::ops::Sub;
+
+use crate::{Key, KeyMap, MapError};
+
+/// Learns a [`KeyMap`] from readings of each key being pressed. Constructed with [`Calibrator::new`].
+///
+/// Resistor tolerances mean a specific module may read slightly differently from the predefined maps.
+/// Guide the user to press each key in turn, [`record`][Calibrator::record] the raw reading
+/// (for example from [`KC11B04::read_raw`][crate::KC11B04::read_raw]), and [`finish`][Calibrator::finish]
+/// to get a map centered on the observed values.
+///
+/// ```rust
+/// use kc11b04::{Calibrator, Key};
+///
+/// let mut calibrator = Calibrator::new();
+/// calibrator.record(Key::K1, 410);
+/// calibrator.record(Key::K2, 600);
+/// calibrator.record(Key::K3, 805);
+/// calibrator.record(Key::K4, 1020);
+///
+/// let map = calibrator.finish().expect("Calibration failed");
+/// assert_eq!(map.key_from_reading(410), Some(Key::K1));
+/// ```
+#[derive(Debug)]
+pub struct Calibrator<Word> {
+	readings: [Option<Word>; 4],
+}
+
+/// Problems found when finishing a [`Calibrator`].
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibError {
+	/// No reading was recorded for this key.
+	Missing(Key),
+	/// The recorded readings don't make a valid map, see [`KeyMap::validate`].
+	Invalid(MapError),
+}
+
+/// Describes the problem, such as `no reading recorded for K1`.
+impl core::fmt::Display for CalibError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			CalibError::Missing(key) => write!(f, "no reading recorded for {}", key),
+			CalibError::Invalid(err) => write!(f, "invalid map: {}", err),
+		}
+	}
+}
+
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+impl std::error::Error for CalibError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			CalibError::Missing(_) => None,
+			CalibError::Invalid(err) => Some(err),
+		}
+	}
+}
+
+impl<Word> Calibrator<Word>
+where
+	Word: Copy + Sub<Output = Word> + Ord + Into<i64> + TryFrom<i64>,
+{
+	/// Create a [`Calibrator`] without any recorded readings.
+	pub fn new() -> Self {
+		Self {
+			readings: [None; 4],
+		}
+	}
+
+	/// Records the raw reading while `key` is pressed, replacing any earlier reading for it.
+	pub fn record(&mut self, key: Key, reading: Word) {
+		self.readings[key.index() as usize] = Some(reading);
+	}
+
+	/// Creates a [`KeyMap`] centered on the recorded readings, with [`KeyMap::from_centers_auto`].
+	///
+	/// The margin is 40% of the smallest gap between two adjacent keys,
+	/// so the bands don't overlap while leaving some room for noise.
+	/// The noise floor is the lower edge of the K1 band.
+	pub fn finish(self) -> Result<KeyMap<Word>, CalibError> {
+		if let Some(key) = Key::all()
+			.into_iter()
+			.find(|key| self.readings[key.index() as usize].is_none())
+		{
+			return Err(CalibError::Missing(key));
+		}
+		let readings = self.readings.map(|r| r.expect("checked above"));
+		KeyMap::from_centers_auto(readings).map_err(CalibError::Invalid)
+	}
+}
+
+impl<Word> Default for Calibrator<Word>
+where
+	Word: Copy + Sub<Output = Word> + Ord + Into<i64> + TryFrom<i64>,
+{
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{CalibError, Calibrator, Key, MapError};
+
+	#[test]
+	fn learns_module_values() {
+		let readings = [420u16, 590, 790, 1010];
+		let mut calibrator = Calibrator::new();
+		for (key, reading) in Key::all().into_iter().zip(readings) {
+			calibrator.record(key, reading);
+		}
+		let map = calibrator.finish().unwrap();
+
+		assert_eq!(
+			readings.map(|r| map.key_from_reading(r)),
+			Key::all().map(Some)
+		);
+		assert_eq!(map.margin, 68);
+		assert_eq!(map.noise_floor, map.k1 - 68);
+	}
+
+	#[test]
+	fn reports_problems() {
+		let mut calibrator = Calibrator::new();
+		calibrator.record(Key::K1, 420u16);
+		calibrator.record(Key::K2, 790);
+		calibrator.record(Key::K4, 1010);
+
+		let mut swapped = Calibrator::new();
+		swapped.record(Key::K1, 420u16);
+		swapped.record(Key::K2, 790);
+		swapped.record(Key::K3, 590);
+		swapped.record(Key::K4, 1010);
+
+		// Keys so close together near `0` that the K1 band would reach an idle reading.
+		let mut cramped = Calibrator::new();
+		for (key, reading) in Key::all().into_iter().zip([10u16, 100, 200, 300]) {
+			cramped.record(key, reading);
+		}
+		assert_eq!(
+			cramped.finish(),
+			Err(CalibError::Invalid(MapError::MarginTooLarge))
+		);
+
+		assert_eq!(
+			(calibrator.finish().err(), swapped.finish().err()),
+			(
+				Some(CalibError::Missing(Key::K3)),
+				Some(CalibError::Invalid(MapError::NonMonotonic {
+					lower: Key::K2,
+					upper: Key::K3
+				}))
+			)
+		);
+	}
+}
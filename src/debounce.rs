@@ -0,0 +1,161 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use embedded_hal::adc::{Channel, OneShot};
+
+use crate::{driver::NbError, Classifier, Key, KeyMap, KC11B04};
+
+/// Wraps a [`KC11B04`] driver to only report a key state once it's been read several times in a row.
+/// Constructed with [`Debounced::new`].
+///
+/// Until a new state is confirmed, the last stable state is reported.
+/// This prevents flickering between keys when a reading sits on the boundary of two bands.
+pub struct Debounced<Pin, ADC, Word, Map = KeyMap<Word>> {
+	keypad: KC11B04<Pin, ADC, Word, Map>,
+	samples: usize,
+	stable: Option<Key>,
+	candidate: Option<Key>,
+	count: usize,
+}
+
+impl<Pin, ADC, Word, Map> Debounced<Pin, ADC, Word, Map>
+where
+	Pin: Channel<ADC>,
+	Word: Copy,
+	Map: Classifier<Word>,
+{
+	/// Wrap a [`KC11B04`] driver, requiring `samples` consecutive agreeing reads to change state.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Debounced, Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 1023),
+	/// # ]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// // Only report a key once it's been read 3 times in a row.
+	/// let mut keypad = Debounced::new(KC11B04::new(analog_pin, MAP_10BIT), 3);
+	///
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(None));
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(None));
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K4)));
+	/// ```
+	pub fn new(keypad: KC11B04<Pin, ADC, Word, Map>, samples: usize) -> Self {
+		Self {
+			keypad,
+			samples,
+			stable: None,
+			candidate: None,
+			count: 0,
+		}
+	}
+
+	/// Takes an ADC reading and returns the last stable key state.
+	///
+	/// Will be [`None`] when no key is pressed, but also for some simultaneous key combinations.
+	pub fn key_state<Adc>(
+		&mut self,
+		adc: &mut Adc,
+	) -> Result<Option<Key>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let key = self.keypad.key_state(adc)?;
+		if key == self.stable {
+			self.count = 0;
+		} else {
+			if key == self.candidate {
+				self.count += 1;
+			} else {
+				self.candidate = key;
+				self.count = 1;
+			}
+			if self.count >= self.samples {
+				self.stable = key;
+				self.count = 0;
+			}
+		}
+		Ok(self.stable)
+	}
+
+	/// Releases the wrapped [`KC11B04`] driver.
+	pub fn into_inner(self) -> KC11B04<Pin, ADC, Word, Map> {
+		self.keypad
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{Classifier, Debounced, Key, KC11B04, MAP_10BIT};
+	use embedded_hal::adc::Channel;
+	use embedded_hal_mock::adc::{Mock, MockChan0, Transaction};
+
+	#[test]
+	fn changes_after_threshold() {
+		use MockChan0 as PIN;
+
+		let readings = [600, 800, 600, 600, 800, 600, 800, 800, 800];
+		let expected = readings.map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = Debounced::new(KC11B04::new(PIN, MAP_10BIT), 2);
+
+		let states = readings.map(|_| keypad.key_state(&mut adc).unwrap());
+
+		assert_eq!(
+			states,
+			[
+				None,
+				None,
+				None,
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K3),
+				Some(Key::K3),
+			]
+		);
+	}
+
+	#[test]
+	fn debounces_custom_classifier() {
+		use MockChan0 as PIN;
+
+		// Only K1 and K2, on either side of the middle of a 10-bit ADC.
+		struct Halves;
+		impl Classifier<u16> for Halves {
+			fn classify(&self, val: u16) -> Option<Key> {
+				match val {
+					0..=99 => None,
+					100..=511 => Some(Key::K1),
+					_ => Some(Key::K2),
+				}
+			}
+		}
+
+		let readings = [200, 200, 900, 200, 900, 900, 0];
+		let expected = readings.map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = Debounced::new(KC11B04::new(PIN, Halves), 2);
+
+		let states = readings.map(|_| keypad.key_state(&mut adc).unwrap());
+
+		assert_eq!(
+			states,
+			[
+				None,
+				Some(Key::K1),
+				Some(Key::K1),
+				Some(Key::K1),
+				Some(Key::K1),
+				Some(Key::K2),
+				Some(Key::K2),
+			]
+		);
+	}
+}
@@ -8,21 +8,88 @@ use core::{
 };
 use embedded_hal::adc::{Channel, OneShot};
 
-use crate::{Key, KeyMap};
+use crate::{hal::BlockingAdc, Classifier, Event, Key, KeyMap, KeyState, MapError};
 
 /// KC11B04 analog keypad driver. Constructed with [`KC11B04::new`].
-pub struct KC11B04<Pin, ADC, Word> {
+///
+/// Readings are classified with a [`KeyMap`] by default, or with your own [`Classifier`].
+pub struct KC11B04<Pin, ADC, Word, Map = KeyMap<Word>> {
 	pin: Pin,
-	map: KeyMap<Word>,
+	pub(crate) map: Map,
+	last: Option<Option<Key>>,
+	prescale: Option<fn(Word) -> Word>,
+	disabled: u8,
 	_adc: PhantomData<ADC>,
 }
 
-type Error<Adc, ADC, Word, Pin> = nb::Error<<Adc as OneShot<ADC, Word, Pin>>::Error>;
+pub(crate) type NbError<Adc, ADC, Word, Pin> = nb::Error<<Adc as OneShot<ADC, Word, Pin>>::Error>;
 
-impl<Pin, ADC, Word> KC11B04<Pin, ADC, Word>
+/// Errors reported by the [`KC11B04`] driver's blocking methods, such as [`KC11B04::key_state_blocking`].
+///
+/// Unlike the [`nb::Error`] from [`KC11B04::key_state`], this doesn't require depending on the `nb` crate.
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error<E> {
+	/// Reading the ADC failed with the HAL's error.
+	Adc(E),
+}
+
+/// The state of the `AD` line, as guessed by [`KC11B04::diagnose`].
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineStatus {
+	/// The readings vary more than the margin, without settling in a key band. The line may be disconnected.
+	Floating,
+	/// Every reading was at or above K4, the line may be shorted to `VCC`.
+	StuckHigh,
+	/// Every reading was below the noise floor, the line may be shorted to `GND`.
+	StuckLow,
+	/// None of the above.
+	Normal,
+}
+
+/// Shows the channel ID and the map, without requiring the pin itself to implement [`Debug`][core::fmt::Debug].
+impl<Pin, ADC, Word, Map> core::fmt::Debug for KC11B04<Pin, ADC, Word, Map>
 where
 	Pin: Channel<ADC>,
-	Word: Copy + Add<Output = Word> + Sub<Output = Word> + Ord,
+	Pin::ID: core::fmt::Debug,
+	Map: core::fmt::Debug,
+{
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("KC11B04")
+			.field("channel", &Pin::channel())
+			.field("map", &self.map)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<E> From<E> for Error<E> {
+	fn from(err: E) -> Self {
+		Error::Adc(err)
+	}
+}
+
+/// Describes the error, including the HAL's error in its [`Debug`][core::fmt::Debug] form.
+impl<E: core::fmt::Debug> core::fmt::Display for Error<E> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			Error::Adc(err) => write!(f, "ADC reading failed: {:?}", err),
+		}
+	}
+}
+
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+impl<E: core::fmt::Debug> std::error::Error for Error<E> {}
+
+impl<Pin, ADC, Word, Map> KC11B04<Pin, ADC, Word, Map>
+where
+	Pin: Channel<ADC>,
+	Word: Copy,
+	Map: Classifier<Word>,
 {
 	/// Create a [`KC11B04`] instance for the given ADC pin / channel and mapping.
 	///
@@ -37,37 +104,613 @@ where
 	/// // providing a map that matches the resolution of your ADC.
 	/// let mut keypad = KC11B04::new(analog_pin, kc11b04::MAP_10BIT);
 	/// ```
-	pub fn new(pin: Pin, map: KeyMap<Word>) -> Self
-	where
-		Pin: Channel<ADC>,
-	{
+	///
+	/// A [`KeyMap`] is [`Copy`], so identical keypads on different channels can share one map.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{KC11B04, KeyMap};
+	/// # let calibrated: KeyMap<u16> = kc11b04::MAP_10BIT;
+	/// let left: KC11B04<_, MockAdc, _> = KC11B04::new(MockChan0, calibrated);
+	/// let right: KC11B04<_, MockAdc, _> = KC11B04::new(MockChan1, calibrated);
+	/// ```
+	pub fn new(pin: Pin, map: Map) -> Self {
 		Self {
 			pin,
 			map,
+			last: None,
+			prescale: None,
+			disabled: 0,
 			_adc: PhantomData,
 		}
 	}
 
+	/// Returns the driver with a function applied to each reading before it's classified.
+	///
+	/// For example, some HALs return 12-bit readings left-aligned in a `u16`, as if it were a 16-bit reading.
+	/// Shifting those down by 4 lets you use [`MAP_12BIT`][crate::MAP_12BIT].
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, MAP_12BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[Transaction::read(0, 0xFFF0)]);
+	/// # let analog_pin = MockChan0;
+	/// let mut keypad = KC11B04::new(analog_pin, MAP_12BIT).with_prescale(|val| val >> 4);
+	///
+	/// assert_eq!(keypad.read_raw(&mut adc), Ok(0x0FFF));
+	/// ```
+	pub fn with_prescale(self, prescale: fn(Word) -> Word) -> Self {
+		Self {
+			prescale: Some(prescale),
+			..self
+		}
+	}
+
+	/// Applies the prescale set with [`KC11B04::with_prescale`], if any.
+	fn prescaled(&self, val: Word) -> Word {
+		match self.prescale {
+			Some(prescale) => prescale(val),
+			None => val,
+		}
+	}
+
 	/// Takes an ADC reading and finds whether a key is currently being pressed.
 	///
 	/// Will be [`None`] when no key is pressed, but also for some simultaneous key combinations.
 	pub fn key_state<Adc>(
 		&mut self,
 		adc: &mut Adc,
-	) -> Result<Option<Key>, Error<Adc, ADC, Word, Pin>>
+	) -> Result<Option<Key>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let val = self.read_raw(adc)?;
+		Ok(self.classify(val))
+	}
+
+	/// Like [`KC11B04::key_state`], but blocks until the ADC reading is done.
+	///
+	/// Returns the crate's own [`Error`], wrapping the HAL's error.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Error, Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # fn main() -> Result<(), Error<embedded_hal_mock::MockError>> {
+	/// # let mut adc = Mock::new(&[Transaction::read(0, 1023)]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// let mut keypad = KC11B04::new(analog_pin, MAP_10BIT);
+	///
+	/// let key = keypad.key_state_blocking(&mut adc)?;
+	/// # assert_eq!(key, Some(Key::K4));
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn key_state_blocking<Adc>(
+		&mut self,
+		adc: &mut Adc,
+	) -> Result<Option<Key>, Error<Adc::Error>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		Ok(nb::block!(self.key_state(adc))?)
+	}
+
+	/// Like [`KC11B04::key_state_blocking`], but for an ADC that returns a plain [`Result`] instead of an [`nb::Result`].
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// use kc11b04::hal::BlockingAdc;
+	/// use kc11b04::{Key, KC11B04, MAP_10BIT};
+	///
+	/// // Stands in for a HAL's ADC with blocking reads.
+	/// struct Adc;
+	///
+	/// impl<Pin> BlockingAdc<u16, Pin> for Adc {
+	/// 	type Error = ();
+	///
+	/// 	fn read(&mut self, _pin: &mut Pin) -> Result<u16, ()> {
+	/// 		Ok(1023)
+	/// 	}
+	/// }
+	///
+	/// # let analog_pin = MockChan0;
+	/// let mut keypad: KC11B04<_, MockAdc, _> = KC11B04::new(analog_pin, MAP_10BIT);
+	/// assert_eq!(keypad.key_state_from_blocking(&mut Adc), Ok(Some(Key::K4)));
+	/// ```
+	pub fn key_state_from_blocking<Adc>(&mut self, adc: &mut Adc) -> Result<Option<Key>, Adc::Error>
+	where
+		Adc: BlockingAdc<Word, Pin>,
+	{
+		self.key_state_with(|pin| adc.read(pin))
+	}
+
+	/// Takes an ADC reading and returns it unprocessed.
+	///
+	/// This is the first thing to reach for when keys aren't detected.
+	/// Logging the raw readings while pressing each key shows whether the module is wired correctly,
+	/// and whether the [`KeyMap`] matches your ADC.
+	///
+	/// The reading has the prescale set with [`KC11B04::with_prescale`] applied, if any.
+	pub fn read_raw<Adc>(&mut self, adc: &mut Adc) -> Result<Word, NbError<Adc, ADC, Word, Pin>>
 	where
 		Adc: OneShot<ADC, Word, Pin>,
 	{
 		let val = adc.read(&mut self.pin)?;
-		Ok(self.map.key_from_reading(val))
+		Ok(self.prescaled(val))
+	}
+
+	/// Classifies a sample taken elsewhere, such as the latest one from a continuously sampling ADC.
+	///
+	/// Some HALs run the ADC in free-running mode, or copy samples to a buffer with DMA,
+	/// rather than triggering a [`OneShot`] reading. Pass the latest sample here instead.
+	/// Like [`KC11B04::read_raw`], the prescale set with [`KC11B04::with_prescale`] is applied.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # let analog_pin = MockChan0;
+	/// // Stands in for a HAL's free-running ADC, or a DMA buffer it writes to.
+	/// struct Continuous {
+	/// 	buffer: [u16; 4],
+	/// 	head: usize,
+	/// }
+	///
+	/// impl Continuous {
+	/// 	fn latest(&self) -> u16 {
+	/// 		self.buffer[self.head]
+	/// 	}
+	/// }
+	///
+	/// let source = Continuous { buffer: [0, 404, 405, 1020], head: 2 };
+	/// let keypad: KC11B04<_, MockAdc, _> = KC11B04::new(analog_pin, MAP_10BIT);
+	///
+	/// assert_eq!(keypad.from_latest(source.latest()), Some(Key::K1));
+	/// ```
+	pub fn from_latest(&self, val: Word) -> Option<Key> {
+		self.classify(self.prescaled(val))
+	}
+
+	/// Like [`KC11B04::key_state`], but takes the reading with the given closure instead of a [`OneShot`] ADC.
+	///
+	/// Useful when the ADC is shared with other peripherals, or when your HAL has its own ADC API.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, MAP_10BIT};
+	/// use core::cell::RefCell;
+	/// use embedded_hal::adc::OneShot;
+	///
+	/// # let adc = RefCell::new(Mock::new(&[Transaction::read(0, 1023)]));
+	/// # let analog_pin = MockChan0;
+	/// # let mut keypad = KC11B04::new(analog_pin, MAP_10BIT);
+	/// // An ADC shared through a `RefCell`, borrowed only for the reading.
+	/// let key = keypad.key_state_with(|pin| nb::block!(adc.borrow_mut().read(pin)));
+	/// # assert_eq!(key, Ok(Some(Key::K4)));
+	/// ```
+	pub fn key_state_with<E, F>(&mut self, mut read_fn: F) -> Result<Option<Key>, E>
+	where
+		F: FnMut(&mut Pin) -> Result<Word, E>,
+	{
+		let val = read_fn(&mut self.pin)?;
+		Ok(self.classify(self.prescaled(val)))
+	}
+
+	/// Like [`KC11B04::key_state`], but reads the given `pin` instead of the one owned by the driver.
+	///
+	/// Useful when the pin is shared, or only borrowed for the duration of a single reading.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[Transaction::read(0, 1023)]);
+	/// let mut shared_pin = MockChan0;
+	/// let mut keypad = KC11B04::new(MockChan0, MAP_10BIT);
+	///
+	/// assert_eq!(keypad.key_state_on(&mut adc, &mut shared_pin), Ok(Some(Key::K4)));
+	/// ```
+	pub fn key_state_on<Adc>(
+		&mut self,
+		adc: &mut Adc,
+		pin: &mut Pin,
+	) -> Result<Option<Key>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let val = adc.read(pin)?;
+		Ok(self.classify(self.prescaled(val)))
+	}
+
+	/// Takes an ADC reading and returns both the key state and the raw reading it was based on.
+	///
+	/// See [`KC11B04::key_state`] and [`KC11B04::read_raw`].
+	#[allow(clippy::type_complexity)]
+	pub fn key_state_with_raw<Adc>(
+		&mut self,
+		adc: &mut Adc,
+	) -> Result<(Option<Key>, Word), NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let val = self.read_raw(adc)?;
+		Ok((self.classify(val), val))
+	}
+
+	/// Takes `n` ADC readings and finds whether a key is pressed based on their mean.
+	///
+	/// This smooths out noise that would make a single reading land outside of a key's band.
+	/// Blocks until all readings are taken. An `n` of `0` is treated as `1`.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[
+	/// # 	Transaction::read(0, 1000),
+	/// # 	Transaction::read(0, 1020),
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 990),
+	/// # ]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// let mut keypad = KC11B04::new(analog_pin, MAP_10BIT);
+	///
+	/// // Average over 4 readings.
+	/// assert_eq!(keypad.key_state_averaged(&mut adc, 4), Ok(Some(Key::K4)));
+	/// ```
+	pub fn key_state_averaged<Adc>(
+		&mut self,
+		adc: &mut Adc,
+		n: usize,
+	) -> Result<Option<Key>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+		Word: Into<i64> + TryFrom<i64>,
+	{
+		let n = n.max(1);
+		let mut sum: i64 = 0;
+		for _ in 0..n {
+			sum += nb::block!(self.read_raw(adc))?.into();
+		}
+		// The mean of `Word` values always fits in `Word`.
+		let mean = match Word::try_from(sum / n as i64) {
+			Ok(mean) => mean,
+			Err(_) => unreachable!(),
+		};
+		Ok(self.classify(mean))
+	}
+
+	/// Takes `N` ADC readings and finds whether a key is pressed based on their median.
+	///
+	/// Unlike [`KC11B04::key_state_averaged`], a short spike in the readings doesn't skew the result.
+	/// The readings are sorted in a `[Word; N]` array on the stack, so keep `N` small and preferably odd.
+	/// For an even `N` the upper of the two middle readings is used.
+	/// Blocks until all readings are taken. An `N` of `0` is treated as `1`.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[
+	/// # 	Transaction::read(0, 1020),
+	/// # 	Transaction::read(0, 0),
+	/// # 	Transaction::read(0, 1023),
+	/// # ]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// let mut keypad = KC11B04::new(analog_pin, MAP_10BIT);
+	///
+	/// // Median of 3 readings.
+	/// assert_eq!(keypad.key_state_median::<3, _>(&mut adc), Ok(Some(Key::K4)));
+	/// ```
+	pub fn key_state_median<const N: usize, Adc>(
+		&mut self,
+		adc: &mut Adc,
+	) -> Result<Option<Key>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+		Word: Ord,
+	{
+		let first = nb::block!(self.read_raw(adc))?;
+		let mut readings = [first; N];
+		for reading in readings.iter_mut().skip(1) {
+			*reading = nb::block!(self.read_raw(adc))?;
+		}
+		readings.sort_unstable();
+		let median = readings.get(N / 2).copied().unwrap_or(first);
+		Ok(self.classify(median))
+	}
+
+	/// Takes an ADC reading and reports how the key state changed since the previous poll.
+	///
+	/// Will be [`None`] when nothing changed.
+	/// The first call only establishes a baseline, so it's always [`None`].
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Event, Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[Transaction::read(0, 0), Transaction::read(0, 1023)]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// let mut keypad = KC11B04::new(analog_pin, MAP_10BIT);
+	///
+	/// assert_eq!(keypad.poll_event(&mut adc), Ok(None));
+	/// assert_eq!(keypad.poll_event(&mut adc), Ok(Some(Event::Pressed(Key::K4))));
+	/// ```
+	pub fn poll_event<Adc>(
+		&mut self,
+		adc: &mut Adc,
+	) -> Result<Option<Event>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let key = self.key_state(adc)?;
+		let event = match self.last.replace(key) {
+			None => None,
+			Some(from) => Event::between(from, key),
+		};
+		Ok(event)
+	}
+
+	/// Takes an ADC reading and returns the key state, along with whether it changed since the previous poll.
+	///
+	/// A lighter alternative to [`KC11B04::poll_event`], sharing its baseline: the first call never reports a change.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[Transaction::read(0, 0), Transaction::read(0, 1023)]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// let mut keypad = KC11B04::new(analog_pin, MAP_10BIT);
+	///
+	/// assert_eq!(keypad.key_state_edge(&mut adc), Ok((None, false)));
+	/// assert_eq!(keypad.key_state_edge(&mut adc), Ok((Some(Key::K4), true)));
+	/// ```
+	#[allow(clippy::type_complexity)]
+	pub fn key_state_edge<Adc>(
+		&mut self,
+		adc: &mut Adc,
+	) -> Result<(Option<Key>, bool), NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let key = self.key_state(adc)?;
+		let changed = matches!(self.last.replace(key), Some(from) if from != key);
+		Ok((key, changed))
+	}
+
+	/// The ID of the ADC channel the driver reads, for example to tell multiple keypads apart in logs.
+	pub fn channel(&self) -> Pin::ID {
+		Pin::channel()
+	}
+
+	/// Takes an ADC reading and calls `handler` with the key being pressed, if any.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # fn main() -> Result<(), nb::Error<embedded_hal_mock::MockError>> {
+	/// # let mut adc = Mock::new(&[Transaction::read(0, 607)]);
+	/// # let analog_pin = MockChan0;
+	/// # let mut keypad = KC11B04::new(analog_pin, MAP_10BIT);
+	/// # let mut volume = 0;
+	/// // Somewhere within loop { }
+	/// keypad.on_key(&mut adc, |key| match key {
+	/// 	Key::K1 => volume -= 1,
+	/// 	Key::K2 => volume += 1,
+	/// 	_ => {}
+	/// })?;
+	/// # assert_eq!(volume, 1);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn on_key<Adc, F>(
+		&mut self,
+		adc: &mut Adc,
+		handler: F,
+	) -> Result<(), NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+		F: FnOnce(Key),
+	{
+		if let Some(key) = self.key_state(adc)? {
+			handler(key);
+		}
+		Ok(())
+	}
+
+	/// Like [`KC11B04::on_key`], but calls `handler` only when the key state changes. See [`KC11B04::poll_event`].
+	pub fn on_event<Adc, F>(
+		&mut self,
+		adc: &mut Adc,
+		handler: F,
+	) -> Result<(), NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+		F: FnOnce(Event),
+	{
+		if let Some(event) = self.poll_event(adc)? {
+			handler(event);
+		}
+		Ok(())
+	}
+
+	/// Stops reporting `key`, treating readings in its band as no key pressed.
+	///
+	/// Useful when a product omits or repurposes one of the buttons, to prevent phantom presses from crosstalk.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[Transaction::read(0, 812)]);
+	/// # let analog_pin = MockChan0;
+	/// let mut keypad = KC11B04::new(analog_pin, MAP_10BIT);
+	/// keypad.disable(Key::K3);
+	///
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(None));
+	/// ```
+	pub fn disable(&mut self, key: Key) {
+		self.disabled |= 1 << key.index();
+	}
+
+	/// Reports `key` again after [`KC11B04::disable`]. All keys are enabled by default.
+	pub fn enable(&mut self, key: Key) {
+		self.disabled &= !(1 << key.index());
+	}
+
+	/// Whether `key` is reported, see [`KC11B04::disable`].
+	pub fn is_enabled(&self, key: Key) -> bool {
+		self.disabled & (1 << key.index()) == 0
+	}
+
+	/// Classifies a reading with the map, leaving out disabled keys.
+	pub(crate) fn classify(&self, val: Word) -> Option<Key> {
+		self.map.classify(val).filter(|&key| self.is_enabled(key))
+	}
+
+	/// Mutable access to the pin / channel, for example to reconfigure it in place.
+	pub fn pin_mut(&mut self) -> &mut Pin {
+		&mut self.pin
+	}
+
+	/// The map used to classify readings.
+	pub fn map(&self) -> &Map {
+		&self.map
+	}
+
+	/// Mutable access to the map used to classify readings, for example to adjust a margin in place.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{KC11B04, MAP_10BIT};
+	/// #
+	/// # let analog_pin = MockChan0;
+	/// let mut keypad: KC11B04<_, MockAdc, _> = KC11B04::new(analog_pin, MAP_10BIT);
+	///
+	/// // Widen the margin to ~6% for a noisy ADC.
+	/// keypad.map_mut().set_margin(61);
+	/// ```
+	pub fn map_mut(&mut self) -> &mut Map {
+		&mut self.map
+	}
+
+	/// Replaces the map used to classify readings, for example with one from a [`Calibrator`][crate::Calibrator].
+	pub fn set_map(&mut self, map: Map) {
+		self.map = map;
+	}
+
+	/// Releases the pin / channel, dropping the driver.
+	pub fn into_inner(self) -> Pin {
+		self.pin
+	}
+}
+
+impl<Pin, ADC, Word> KC11B04<Pin, ADC, Word>
+where
+	Pin: Channel<ADC>,
+	Word: Copy + Add<Output = Word> + Sub<Output = Word> + Ord + TryFrom<i64>,
+{
+	/// Like [`KC11B04::new`], but first checks the map with [`KeyMap::validate`], refusing invalid maps.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{KC11B04, MAP_10BIT};
+	/// #
+	/// # let analog_pin = MockChan0;
+	/// let keypad: KC11B04<_, MockAdc, _> = KC11B04::try_new(analog_pin, MAP_10BIT.with_margin(60))
+	/// 	.expect("bands of a 6% margin should not overlap");
+	/// ```
+	pub fn try_new(pin: Pin, map: KeyMap<Word>) -> Result<Self, MapError> {
+		map.validate()?;
+		Ok(Self::new(pin, map))
+	}
+
+	/// Like [`KC11B04::key_state`], but tells an idle keypad apart from an unrecognized reading.
+	///
+	/// See [`KeyMap::key_state_from_reading`].
+	pub fn key_state_detailed<Adc>(
+		&mut self,
+		adc: &mut Adc,
+	) -> Result<KeyState, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let val = self.read_raw(adc)?;
+		let state = match self.map.key_state_from_reading(val) {
+			KeyState::Pressed(key) if !self.is_enabled(key) => KeyState::None,
+			state => state,
+		};
+		Ok(state)
+	}
+
+	/// Takes `samples` ADC readings, at least one, and guesses whether the `AD` line is wired correctly, blocking until done.
+	///
+	/// This is a heuristic, meant to run while holding one of K1 to K3, such as when prompting the user during setup.
+	/// It checks in this order:
+	///
+	/// - [`LineStatus::StuckHigh`] when every reading is at or above K4. Holding K4 reads the same.
+	/// - [`LineStatus::StuckLow`] when every reading is below the [`noise_floor`][KeyMap::noise_floor],
+	///   even if noisy. An idle keypad reads the same, which is why a key should be held.
+	/// - [`LineStatus::Floating`] when the readings spread further than the margin,
+	///   while not all in the same key band.
+	/// - [`LineStatus::Normal`] otherwise.
+	pub fn diagnose<Adc>(
+		&mut self,
+		adc: &mut Adc,
+		samples: usize,
+	) -> Result<LineStatus, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+		Word: Into<i64>,
+	{
+		let first = nb::block!(self.read_raw(adc))?;
+		let (mut min, mut max) = (first, first);
+		let mut same_key = self.map.key_from_reading(first).is_some();
+		for _ in 1..samples {
+			let val = nb::block!(self.read_raw(adc))?;
+			same_key &= self.map.key_from_reading(val) == self.map.key_from_reading(first);
+			min = min.min(val);
+			max = max.max(val);
+		}
+
+		// In `i64`, as the spread may not fit in `Word` for signed readings far apart.
+		let spread = max.into() - min.into();
+		let status = if min >= self.map.k4 {
+			LineStatus::StuckHigh
+		} else if max < self.map.noise_floor {
+			LineStatus::StuckLow
+		} else if !same_key && spread > self.map.margin.into() {
+			LineStatus::Floating
+		} else {
+			LineStatus::Normal
+		};
+		Ok(status)
 	}
 }
 
 #[cfg(test)]
 mod test {
-	use crate::{Key, KC11B04, MAP_10BIT};
+	extern crate std;
+
+	use crate::{
+		Error, Event, Key, KeyState, LineStatus, MapError, KC11B04, MAP_10BIT, MAP_12BIT,
+		MAP_12BIT_SIGNED,
+	};
 	use embedded_hal::adc::Channel;
-	use embedded_hal_mock::adc::{Mock, MockChan0, Transaction};
+	use embedded_hal_mock::{
+		adc::{Mock, MockAdc, MockChan0, MockChan1, Transaction},
+		MockError,
+	};
+	use std::io::ErrorKind;
 
 	#[test]
 	fn reads_given_channel() {
@@ -101,4 +744,415 @@ mod test {
 			)
 		);
 	}
+
+	#[test]
+	fn reports_events() {
+		use MockChan0 as PIN;
+
+		let expected = [
+			Transaction::read(PIN::channel(), 0),
+			Transaction::read(PIN::channel(), 400),
+			Transaction::read(PIN::channel(), 400),
+			Transaction::read(PIN::channel(), 600),
+			Transaction::read(PIN::channel(), 0),
+		];
+
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+
+		assert_eq!(
+			(
+				keypad.poll_event(&mut adc),
+				keypad.poll_event(&mut adc),
+				keypad.poll_event(&mut adc),
+				keypad.poll_event(&mut adc),
+				keypad.poll_event(&mut adc)
+			),
+			(
+				Ok(None),
+				Ok(Some(Event::Pressed(Key::K1))),
+				Ok(None),
+				Ok(Some(Event::Changed {
+					from: Some(Key::K1),
+					to: Some(Key::K2)
+				})),
+				Ok(Some(Event::Released(Key::K2)))
+			)
+		);
+	}
+
+	#[test]
+	fn averages_noisy_readings() {
+		use MockChan0 as PIN;
+
+		// Individually these would read as None, None, K2.
+		let expected = [
+			Transaction::read(PIN::channel(), 570),
+			Transaction::read(PIN::channel(), 650),
+			Transaction::read(PIN::channel(), 600),
+		];
+
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+
+		assert_eq!(keypad.key_state_averaged(&mut adc, 3), Ok(Some(Key::K2)));
+	}
+
+	#[test]
+	fn averages_signed_readings() {
+		use MockChan0 as PIN;
+
+		let expected = [
+			Transaction::read(PIN::channel(), 1170i16),
+			Transaction::read(PIN::channel(), 1260),
+			Transaction::read(PIN::channel(), 1212),
+		];
+
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_12BIT_SIGNED);
+
+		assert_eq!(keypad.key_state_averaged(&mut adc, 3), Ok(Some(Key::K2)));
+	}
+
+	#[test]
+	fn median_rejects_spike() {
+		use MockChan0 as PIN;
+
+		let expected = [
+			Transaction::read(PIN::channel(), 600),
+			Transaction::read(PIN::channel(), 1023),
+			Transaction::read(PIN::channel(), 605),
+		];
+
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+		assert_eq!(keypad.key_state_median::<3, _>(&mut adc), Ok(Some(Key::K2)));
+
+		// While the mean of the same readings lands in the gap between K2 and K3.
+		let mut adc = Mock::new(&expected);
+		assert_eq!(keypad.key_state_averaged(&mut adc, 3), Ok(None));
+	}
+
+	#[test]
+	fn propagates_errors() {
+		use MockChan0 as PIN;
+
+		let expected = [
+			Transaction::read(PIN::channel(), 0).with_error(MockError::Io(ErrorKind::TimedOut)),
+			Transaction::read(PIN::channel(), 1023),
+		];
+
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+
+		assert_eq!(
+			(
+				keypad.key_state_blocking(&mut adc),
+				keypad.key_state_blocking(&mut adc),
+			),
+			(
+				Err(Error::Adc(MockError::Io(ErrorKind::TimedOut))),
+				Ok(Some(Key::K4))
+			)
+		);
+	}
+
+	#[test]
+	fn reports_raw_readings() {
+		use MockChan0 as PIN;
+
+		let expected = [
+			Transaction::read(PIN::channel(), 123),
+			Transaction::read(PIN::channel(), 600),
+		];
+
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+
+		assert_eq!(
+			(
+				keypad.read_raw(&mut adc),
+				keypad.key_state_with_raw(&mut adc)
+			),
+			(Ok(123), Ok((Some(Key::K2), 600)))
+		);
+	}
+
+	#[test]
+	fn reads_with_closure() {
+		let mut readings = [0, 1023, 600].into_iter();
+		let mut keypad = KC11B04::<_, MockAdc, u16>::new(MockChan0, MAP_10BIT);
+		let mut read = |_: &mut MockChan0| readings.next().ok_or(());
+
+		assert_eq!(
+			[(); 4].map(|_| keypad.key_state_with(&mut read)),
+			[Ok(None), Ok(Some(Key::K4)), Ok(Some(Key::K2)), Err(())]
+		);
+	}
+
+	#[test]
+	fn refuses_invalid_maps() {
+		assert_eq!(
+			KC11B04::<_, MockAdc, u16>::try_new(MockChan0, MAP_10BIT.with_margin(102)).err(),
+			Some(MapError::Overlap {
+				lower: Key::K1,
+				upper: Key::K2
+			})
+		);
+		assert!(KC11B04::<_, MockAdc, u16>::try_new(MockChan0, MAP_10BIT).is_ok());
+	}
+
+	#[test]
+	fn prescales_left_aligned_readings() {
+		use MockChan0 as PIN;
+
+		// 12-bit readings, left-aligned in 16 bits.
+		let readings = [0, 1617, 2428, 3250, 4095].map(|r| r << 4);
+		let expected = readings.map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_12BIT).with_prescale(|val| val >> 4);
+
+		assert_eq!(
+			readings.map(|_| keypad.key_state(&mut adc).unwrap()),
+			[
+				None,
+				Some(Key::K1),
+				Some(Key::K2),
+				Some(Key::K3),
+				Some(Key::K4)
+			]
+		);
+	}
+
+	#[test]
+	fn reports_channel() {
+		let keypad = KC11B04::<_, MockAdc, u16>::new(MockChan1, MAP_10BIT);
+		assert_eq!(keypad.channel(), 1);
+	}
+
+	#[test]
+	fn dispatches_keys_and_events() {
+		use MockChan0 as PIN;
+
+		let expected =
+			[0, 607, 607, 0, 0, 607, 607, 0].map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+
+		let mut keys = [None; 4];
+		for key in &mut keys {
+			keypad.on_key(&mut adc, |k| *key = Some(k)).unwrap();
+		}
+		assert_eq!(keys, [None, Some(Key::K2), Some(Key::K2), None]);
+
+		let mut events = [None; 4];
+		for event in &mut events {
+			keypad.on_event(&mut adc, |e| *event = Some(e)).unwrap();
+		}
+		assert_eq!(
+			events,
+			[
+				None,
+				Some(Event::Pressed(Key::K2)),
+				None,
+				Some(Event::Released(Key::K2))
+			]
+		);
+	}
+
+	#[test]
+	fn diagnoses_line() {
+		use MockChan0 as PIN;
+
+		let lines = [
+			[1023, 1023, 1023, 1023],
+			[0, 0, 0, 0],
+			[3, 0, 5, 1],
+			[12, 310, 95, 520],
+			[604, 610, 607, 606],
+		];
+		let expected: std::vec::Vec<_> = lines
+			.concat()
+			.into_iter()
+			.map(|r| Transaction::read(PIN::channel(), r))
+			.collect();
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+
+		assert_eq!(
+			lines.map(|_| keypad.diagnose(&mut adc, 4).unwrap()),
+			[
+				LineStatus::StuckHigh,
+				LineStatus::StuckLow,
+				LineStatus::StuckLow,
+				LineStatus::Floating,
+				LineStatus::Normal
+			]
+		);
+	}
+
+	#[test]
+	fn diagnoses_signed_swings() {
+		use MockChan0 as PIN;
+
+		let readings = [-30000i16, 30000, -30000, 30000];
+		let expected = readings.map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_12BIT_SIGNED);
+
+		assert_eq!(keypad.diagnose(&mut adc, 4), Ok(LineStatus::Floating));
+	}
+
+	#[test]
+	fn classifies_latest_samples() {
+		let keypad = KC11B04::<_, MockAdc, u16>::new(MockChan0, MAP_10BIT);
+		assert_eq!(
+			[0, 400, 500, 600, 700, 800, 1023].map(|r| keypad.from_latest(r)),
+			[
+				None,
+				Some(Key::K1),
+				None,
+				Some(Key::K2),
+				None,
+				Some(Key::K3),
+				Some(Key::K4)
+			]
+		);
+	}
+
+	#[test]
+	fn adjusts_map_in_place() {
+		use MockChan0 as PIN;
+
+		let expected = [650; 3].map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+
+		assert_eq!(keypad.key_state(&mut adc), Ok(None));
+		keypad.map_mut().set_margin(60);
+		assert_eq!(keypad.map().margin, 60);
+		assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K2)));
+		keypad.set_map(MAP_10BIT);
+		assert_eq!(keypad.key_state(&mut adc), Ok(None));
+	}
+
+	#[test]
+	fn reads_borrowed_pin() {
+		use MockChan1 as PIN;
+
+		let expected = [607, 812].map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut pin = PIN;
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+
+		let borrowed = &mut pin;
+		assert_eq!(keypad.key_state_on(&mut adc, borrowed), Ok(Some(Key::K2)));
+		assert_eq!(keypad.key_state_on(&mut adc, borrowed), Ok(Some(Key::K3)));
+		let _: MockChan1 = pin;
+	}
+
+	#[test]
+	fn shares_map_between_drivers() {
+		let expected = [
+			Transaction::read(MockChan0::channel(), 607),
+			Transaction::read(MockChan1::channel(), 1023),
+		];
+		let mut adc = Mock::new(&expected);
+		let map = MAP_10BIT;
+		let mut left = KC11B04::new(MockChan0, map);
+		let mut right = KC11B04::new(MockChan1, map);
+
+		assert_eq!(left.key_state(&mut adc), Ok(Some(Key::K2)));
+		assert_eq!(right.key_state(&mut adc), Ok(Some(Key::K4)));
+		assert_eq!((left.map(), right.map()), (&map, &map));
+	}
+
+	#[test]
+	fn formats_driver() {
+		let map = crate::KeyMap {
+			k1: 400,
+			k2: 600,
+			k3: 800,
+			k4: 1000,
+			margin: 20,
+			margin_high: 20,
+			noise_floor: 380,
+		};
+		let keypad = KC11B04::<_, MockAdc, u16>::new(MockChan1, map);
+		assert_eq!(
+			std::format!("{:?}", keypad),
+			"KC11B04 { channel: 1, map: KeyMap { k1: 400, k2: 600, k3: 800, k4: 1000, \
+			margin: 20, margin_high: 20, noise_floor: 380 }, .. }"
+		);
+	}
+
+	#[test]
+	fn reads_blocking_adc() {
+		use crate::hal::BlockingAdc;
+
+		struct Blocking<'a>(&'a [Result<u16, MockError>]);
+		impl<'a> BlockingAdc<u16, MockChan0> for Blocking<'a> {
+			type Error = MockError;
+
+			fn read(&mut self, _pin: &mut MockChan0) -> Result<u16, MockError> {
+				let (first, rest) = self.0.split_first().unwrap();
+				self.0 = rest;
+				first.clone()
+			}
+		}
+
+		let err = MockError::Io(ErrorKind::Other);
+		let readings = [Ok(607), Err(err.clone())];
+		let mut adc = Blocking(&readings);
+		let mut keypad = KC11B04::<_, MockAdc, _>::new(MockChan0, MAP_10BIT);
+
+		assert_eq!(keypad.key_state_from_blocking(&mut adc), Ok(Some(Key::K2)));
+		assert_eq!(keypad.key_state_from_blocking(&mut adc), Err(err));
+	}
+
+	#[test]
+	fn disables_keys() {
+		use MockChan0 as PIN;
+
+		let expected = [812, 607, 812, 812].map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+
+		keypad.disable(Key::K3);
+		assert!(!keypad.is_enabled(Key::K3) && keypad.is_enabled(Key::K2));
+		assert_eq!(keypad.key_state(&mut adc), Ok(None));
+		assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K2)));
+		assert_eq!(keypad.key_state_detailed(&mut adc), Ok(KeyState::None));
+		keypad.enable(Key::K3);
+		assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K3)));
+	}
+
+	#[test]
+	fn reports_edges() {
+		use MockChan0 as PIN;
+
+		let readings = [607, 607, 607, 812, 812, 0];
+		let expected = readings.map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = KC11B04::new(PIN, MAP_10BIT);
+
+		assert_eq!(
+			readings.map(|_| keypad.key_state_edge(&mut adc).unwrap()),
+			[
+				(Some(Key::K2), false),
+				(Some(Key::K2), false),
+				(Some(Key::K2), false),
+				(Some(Key::K3), true),
+				(Some(Key::K3), false),
+				(None, true),
+			]
+		);
+	}
+
+	#[test]
+	fn releases_pin() {
+		let mut keypad = KC11B04::<_, MockAdc, u16>::new(MockChan1, MAP_10BIT);
+		let _: &mut MockChan1 = keypad.pin_mut();
+		let _: MockChan1 = keypad.into_inner();
+	}
 }
@@ -0,0 +1,29 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Re-exports the `embedded-hal` 0.2 ADC traits the driver is built on,
+//! and defines [`BlockingAdc`] for HALs whose ADC reads return a plain [`Result`].
+//!
+//! Saves depending on, and keeping in sync with, `embedded-hal` for just these traits,
+//! such as to get a pin's ID with [`Channel::channel`].
+//!
+//! ```rust
+//! use kc11b04::hal::Channel;
+//! # use embedded_hal_mock::adc::MockChan1;
+//!
+//! assert_eq!(MockChan1::channel(), 1);
+//! ```
+
+pub use embedded_hal::adc::{Channel, OneShot};
+
+/// An ADC whose reads block until done and return a plain [`Result`], rather than the [`nb::Result`] of [`OneShot`].
+///
+/// Implement it for your HAL's ADC to use [`KC11B04::key_state_from_blocking`][crate::KC11B04::key_state_from_blocking].
+pub trait BlockingAdc<Word, Pin> {
+	/// The HAL's error for failed readings.
+	type Error;
+
+	/// Reads `pin`, blocking until the reading is done.
+	fn read(&mut self, pin: &mut Pin) -> Result<Word, Self::Error>;
+}
@@ -0,0 +1,127 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use core::ops::{Add, Sub};
+use embedded_hal::adc::{Channel, OneShot};
+
+use crate::{
+	driver::NbError,
+	mapping::{within_above, within_below},
+	Key, KC11B04,
+};
+
+/// Wraps a [`KC11B04`] driver to latch onto the last reported key. Constructed with [`Hysteresis::new`].
+///
+/// Once a key is reported, its band is widened, so a reading has to clearly leave it before switching to
+/// another key, or to no key. This prevents chatter when a held key reads right at the edge of its band.
+pub struct Hysteresis<Pin, ADC, Word> {
+	keypad: KC11B04<Pin, ADC, Word>,
+	widen: Word,
+	latched: Option<Key>,
+}
+
+impl<Pin, ADC, Word> Hysteresis<Pin, ADC, Word>
+where
+	Pin: Channel<ADC>,
+	Word: Copy + Add<Output = Word> + Sub<Output = Word> + Ord + TryFrom<i64>,
+{
+	/// Wrap a [`KC11B04`] driver, widening the band of the latched key by `widen` on either side.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Hysteresis, Key, KC11B04, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[Transaction::read(0, 630), Transaction::read(0, 650)]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// // Widen the band of a pressed key by 20 in either direction.
+	/// let mut keypad = Hysteresis::new(KC11B04::new(analog_pin, MAP_10BIT), 20);
+	///
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K2)));
+	/// // Slightly outside of the regular K2 band.
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K2)));
+	/// ```
+	pub fn new(keypad: KC11B04<Pin, ADC, Word>, widen: Word) -> Self {
+		Self {
+			keypad,
+			widen,
+			latched: None,
+		}
+	}
+
+	/// Takes an ADC reading and finds whether a key is currently being pressed,
+	/// preferring the latched key while the reading is within its widened band.
+	///
+	/// Will be [`None`] when no key is pressed, but also for some simultaneous key combinations.
+	pub fn key_state<Adc>(
+		&mut self,
+		adc: &mut Adc,
+	) -> Result<Option<Key>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let val = self.keypad.read_raw(adc)?;
+		let key = match self.latched {
+			Some(key) if self.in_widened_band(key, val) => Some(key),
+			_ => self.keypad.classify(val),
+		};
+		self.latched = key;
+		Ok(key)
+	}
+
+	/// Releases the wrapped [`KC11B04`] driver.
+	pub fn into_inner(self) -> KC11B04<Pin, ADC, Word> {
+		self.keypad
+	}
+
+	fn in_widened_band(&self, key: Key, val: Word) -> bool {
+		let map = &self.keypad.map;
+		let expected = map.expected(key);
+		if key == Key::K4 && val >= expected {
+			return true;
+		}
+		if val >= expected {
+			// Past the margin `val - margin_high` is above `expected`, so it can't overflow.
+			within_above(val, expected, map.margin_high)
+				|| within_above(val - map.margin_high, expected, self.widen)
+		} else {
+			// Past the margin `val + margin` is below `expected`, so it can't overflow.
+			within_below(val, expected, map.margin)
+				|| within_below(val + map.margin, expected, self.widen)
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{Hysteresis, Key, KC11B04, MAP_10BIT};
+	use embedded_hal::adc::Channel;
+	use embedded_hal_mock::adc::{Mock, MockChan0, Transaction};
+
+	#[test]
+	fn stays_latched_near_boundary() {
+		use MockChan0 as PIN;
+
+		// Oscillating at the upper edge of K2, before clearly moving into K3 and back.
+		let readings = [630, 650, 630, 655, 790, 770, 606];
+		let expected = readings.map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = Hysteresis::new(KC11B04::new(PIN, MAP_10BIT), 20);
+
+		let states = readings.map(|_| keypad.key_state(&mut adc).unwrap());
+
+		assert_eq!(
+			states,
+			[
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K3),
+				Some(Key::K3),
+				Some(Key::K2),
+			]
+		);
+	}
+}
@@ -0,0 +1,192 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use embedded_hal::adc::{Channel, OneShot};
+
+use crate::{driver::NbError, Classifier, Key, KeyMap, KC11B04};
+
+/// Wraps a [`KC11B04`] driver in a state machine that can be queried after each update. Constructed with [`Keypad::new`].
+///
+/// Rather than reporting events, it keeps track of the current key and how long it's been pressed,
+/// similar to input APIs common in games.
+///
+/// The crate doesn't depend on any clock. Instead every update takes the current time in ticks,
+/// from whatever monotonic timer or counter is available. The tick counter may wrap around.
+pub struct Keypad<Pin, ADC, Word, Map = KeyMap<Word>> {
+	keypad: KC11B04<Pin, ADC, Word, Map>,
+	current: Option<Key>,
+	previous: Option<Key>,
+	since: u32,
+	state_ticks: u32,
+}
+
+impl<Pin, ADC, Word, Map> Keypad<Pin, ADC, Word, Map>
+where
+	Pin: Channel<ADC>,
+	Word: Copy,
+	Map: Classifier<Word>,
+{
+	/// Wrap a [`KC11B04`] driver, starting with no key pressed.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, Keypad, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 1023),
+	/// # ]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// let mut keypad = Keypad::new(KC11B04::new(analog_pin, MAP_10BIT));
+	///
+	/// keypad.update(&mut adc, 0).unwrap();
+	/// assert!(keypad.just_pressed(Key::K4));
+	///
+	/// keypad.update(&mut adc, 300).unwrap();
+	/// assert!(keypad.is_pressed(Key::K4) && !keypad.just_pressed(Key::K4));
+	/// assert_eq!(keypad.held_for(Key::K4), 300);
+	/// ```
+	pub fn new(keypad: KC11B04<Pin, ADC, Word, Map>) -> Self {
+		Self {
+			keypad,
+			current: None,
+			previous: None,
+			since: 0,
+			state_ticks: 0,
+		}
+	}
+
+	/// Takes an ADC reading at time `now` in ticks, and updates the key state.
+	pub fn update<Adc>(
+		&mut self,
+		adc: &mut Adc,
+		now: u32,
+	) -> Result<(), NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let key = self.keypad.key_state(adc)?;
+		self.previous = self.current;
+		self.current = key;
+		if self.previous != self.current {
+			self.since = now;
+		}
+		self.state_ticks = now.wrapping_sub(self.since);
+		Ok(())
+	}
+
+	/// The key pressed as of the last update.
+	pub fn current(&self) -> Option<Key> {
+		self.current
+	}
+
+	/// Whether `key` was pressed as of the last update.
+	pub fn is_pressed(&self, key: Key) -> bool {
+		self.current == Some(key)
+	}
+
+	/// Whether `key` became pressed in the last update.
+	pub fn just_pressed(&self, key: Key) -> bool {
+		self.is_pressed(key) && self.previous != Some(key)
+	}
+
+	/// Whether `key` stopped being pressed in the last update.
+	pub fn just_released(&self, key: Key) -> bool {
+		self.previous == Some(key) && !self.is_pressed(key)
+	}
+
+	/// How many ticks `key` has been pressed for, as of the last update. `0` when it isn't pressed.
+	pub fn held_for(&self, key: Key) -> u32 {
+		if self.is_pressed(key) {
+			self.state_ticks
+		} else {
+			0
+		}
+	}
+
+	/// How many ticks no key has been pressed for, as of the last update. `0` while a key is pressed.
+	///
+	/// Until the first key press it counts from tick `0`. Useful for idle timeouts, such as going to sleep.
+	pub fn released_for(&self) -> u32 {
+		if self.current.is_none() {
+			self.state_ticks
+		} else {
+			0
+		}
+	}
+
+	/// Releases the wrapped [`KC11B04`] driver.
+	pub fn into_inner(self) -> KC11B04<Pin, ADC, Word, Map> {
+		self.keypad
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{Key, Keypad, KC11B04, MAP_10BIT};
+	use embedded_hal::adc::Channel;
+	use embedded_hal_mock::adc::{Mock, MockChan0, Transaction};
+
+	#[test]
+	fn taps_and_holds() {
+		use MockChan0 as PIN;
+
+		let updates = [
+			// Tap K1.
+			(0, 0),
+			(400, 100),
+			(0, 200),
+			// Hold K2.
+			(600, 300),
+			(600, 700),
+			(600, 900),
+			(0, 1000),
+		];
+		let expected = updates.map(|(r, _)| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = Keypad::new(KC11B04::new(PIN, MAP_10BIT));
+
+		let states = updates.map(|(_, now)| {
+			keypad.update(&mut adc, now).unwrap();
+			(
+				keypad.current(),
+				keypad.just_pressed(Key::K1) || keypad.just_pressed(Key::K2),
+				keypad.just_released(Key::K1) || keypad.just_released(Key::K2),
+				keypad.held_for(Key::K2),
+			)
+		});
+
+		assert_eq!(
+			states,
+			[
+				(None, false, false, 0),
+				(Some(Key::K1), true, false, 0),
+				(None, false, true, 0),
+				(Some(Key::K2), true, false, 0),
+				(Some(Key::K2), false, false, 400),
+				(Some(Key::K2), false, false, 600),
+				(None, false, true, 0),
+			]
+		);
+		assert!(!keypad.is_pressed(Key::K2));
+	}
+
+	#[test]
+	fn tracks_idle_time() {
+		use MockChan0 as PIN;
+
+		let updates = [(0, 100), (0, 250), (0, 400), (600, 500), (0, 600), (0, 900)];
+		let expected = updates.map(|(r, _)| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = Keypad::new(KC11B04::new(PIN, MAP_10BIT));
+
+		let idle = updates.map(|(_, now)| {
+			keypad.update(&mut adc, now).unwrap();
+			keypad.released_for()
+		});
+
+		assert_eq!(idle, [100, 250, 400, 0, 0, 300]);
+	}
+}
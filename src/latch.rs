@@ -0,0 +1,113 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use embedded_hal::adc::{Channel, OneShot};
+
+use crate::{driver::NbError, Classifier, Key, KeyMap, KC11B04};
+
+/// Wraps a [`KC11B04`] driver to keep reporting the last key through a few missed readings.
+/// Constructed with [`LatchGuard::new`].
+///
+/// When a finger briefly lifts, or noise hits a single reading, the key isn't released right away.
+/// Only once no key has been read several times in a row is the release reported.
+/// Changing to another key is reported immediately.
+pub struct LatchGuard<Pin, ADC, Word, Map = KeyMap<Word>> {
+	keypad: KC11B04<Pin, ADC, Word, Map>,
+	hold: usize,
+	latched: Option<Key>,
+	missed: usize,
+}
+
+impl<Pin, ADC, Word, Map> LatchGuard<Pin, ADC, Word, Map>
+where
+	Pin: Channel<ADC>,
+	Word: Copy,
+	Map: Classifier<Word>,
+{
+	/// Wrap a [`KC11B04`] driver, holding the last key for up to `hold` consecutive reads without a key.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, LatchGuard, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 0),
+	/// # 	Transaction::read(0, 0),
+	/// # ]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// // Ride through a single missed reading.
+	/// let mut keypad = LatchGuard::new(KC11B04::new(analog_pin, MAP_10BIT), 1);
+	///
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K4)));
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K4)));
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(None));
+	/// ```
+	pub fn new(keypad: KC11B04<Pin, ADC, Word, Map>, hold: usize) -> Self {
+		Self {
+			keypad,
+			hold,
+			latched: None,
+			missed: 0,
+		}
+	}
+
+	/// Takes an ADC reading and returns the key state, holding the last key through missed readings.
+	///
+	/// Will be [`None`] when no key is pressed, but also for some simultaneous key combinations.
+	pub fn key_state<Adc>(
+		&mut self,
+		adc: &mut Adc,
+	) -> Result<Option<Key>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		match self.keypad.key_state(adc)? {
+			Some(key) => {
+				self.latched = Some(key);
+				self.missed = 0;
+			}
+			None if self.missed < self.hold => self.missed += 1,
+			None => self.latched = None,
+		}
+		Ok(self.latched)
+	}
+
+	/// Releases the wrapped [`KC11B04`] driver.
+	pub fn into_inner(self) -> KC11B04<Pin, ADC, Word, Map> {
+		self.keypad
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{Key, LatchGuard, KC11B04, MAP_10BIT};
+	use embedded_hal::adc::Channel;
+	use embedded_hal_mock::adc::{Mock, MockChan0, Transaction};
+
+	#[test]
+	fn holds_through_missed_readings() {
+		use MockChan0 as PIN;
+
+		let readings = [600, 0, 600, 0, 0, 800];
+		let expected = readings.map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = LatchGuard::new(KC11B04::new(PIN, MAP_10BIT), 1);
+
+		let states = readings.map(|_| keypad.key_state(&mut adc).unwrap());
+
+		assert_eq!(
+			states,
+			[
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K2),
+				None,
+				Some(Key::K3),
+			]
+		);
+	}
+}
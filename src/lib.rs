@@ -49,6 +49,23 @@
 //! }
 //! ```
 //!
+//! # embedded-hal 1.0
+//!
+//! The driver is built on the `embedded_hal::adc` traits from `embedded-hal` 0.2.
+//! `embedded-hal` 1.0 no longer includes any ADC traits, so there is no equivalent to implement against.
+//! Neither does `embedded-hal-async`, so the same goes for async ADC reads.
+//!
+//! With a HAL that offers its own ADC API, take the reading yourself
+//! and classify it with [`KeyMap::key_from_reading`] instead.
+//!
+//! ```rust
+//! use kc11b04::{Key, MAP_10BIT};
+//!
+//! let reading: u16 = /* Read the pin connected to AD using your HAL */
+//! # 1023;
+//! assert_eq!(MAP_10BIT.key_from_reading(reading), Some(Key::K4));
+//! ```
+//!
 //! # MSRV policy
 //!
 //! Upgrading the MSRV is considered SemVer breaking.
@@ -63,16 +80,43 @@
 	doc = "[kc11b04-image]: docs/KC11B04.webp"
 )]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+mod auto_repeat;
+mod calibrator;
+mod debounce;
 mod driver;
+pub mod hal;
+mod hysteresis;
+mod keypad;
+mod latch;
+mod long_press;
 pub mod mapping;
+pub mod prelude;
+mod rolling_debounce;
+mod stats;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 
+pub use auto_repeat::*;
+pub use calibrator::*;
+pub use debounce::*;
 pub use driver::*;
-pub use mapping::KeyMap;
+pub use hysteresis::*;
+pub use keypad::*;
+pub use latch::*;
+pub use long_press::*;
+use mapping::predefined_map;
+pub use mapping::{Classifier, KeyMap, MapError, NoKey};
+pub use rolling_debounce::*;
+pub use stats::*;
 
 /// A named key on the [KC11B04][crate] module.
 #[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
 #[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Key {
 	/// `K1` key on the [KC11B04][crate] module.
 	K1 = 1,
@@ -84,23 +128,481 @@ pub enum Key {
 	K4,
 }
 
+impl Key {
+	/// The number of keys, for per-key tables like `[Action; Key::COUNT]`.
+	pub const COUNT: usize = 4;
+
+	/// All keys, in order from K1 to K4.
+	pub const fn all() -> [Key; Key::COUNT] {
+		[Key::K1, Key::K2, Key::K3, Key::K4]
+	}
+
+	/// The zero-based index of the key, `0` for K1 up to `3` for K4.
+	pub const fn index(self) -> u8 {
+		self as u8 - 1
+	}
+
+	/// The key at zero-based `index`, the inverse of [`Key::index`]. `None` from `4` on.
+	pub const fn from_index(index: u8) -> Option<Key> {
+		match index {
+			0 => Some(Key::K1),
+			1 => Some(Key::K2),
+			2 => Some(Key::K3),
+			3 => Some(Key::K4),
+			_ => None,
+		}
+	}
+
+	/// The key's number as an ASCII byte, `b'1'` for K1 up to `b'4'` for K4.
+	///
+	/// Like the [`char`] conversion, but for byte-oriented transports such as a serial port.
+	pub const fn as_byte(self) -> u8 {
+		b'0' + self as u8
+	}
+
+	/// The key for an ASCII byte, the inverse of [`Key::as_byte`]. `None` for anything but `b'1'` to `b'4'`.
+	pub const fn from_byte(byte: u8) -> Option<Key> {
+		match byte {
+			b'1' => Some(Key::K1),
+			b'2' => Some(Key::K2),
+			b'3' => Some(Key::K3),
+			b'4' => Some(Key::K4),
+			_ => None,
+		}
+	}
+}
+
+/// Writes the key's name, `K1` up to `K4`.
+impl core::fmt::Display for Key {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str(match self {
+			Key::K1 => "K1",
+			Key::K2 => "K2",
+			Key::K3 => "K3",
+			Key::K4 => "K4",
+		})
+	}
+}
+
+/// The key's number as a label, `'1'` for K1 up to `'4'` for K4.
+impl From<Key> for char {
+	fn from(key: Key) -> Self {
+		key.as_byte() as char
+	}
+}
+
+/// Converts from the key's number, `1` for K1 up to `4` for K4.
+impl TryFrom<u8> for Key {
+	type Error = InvalidKey;
+
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			1 => Ok(Key::K1),
+			2 => Ok(Key::K2),
+			3 => Ok(Key::K3),
+			4 => Ok(Key::K4),
+			_ => Err(InvalidKey(value)),
+		}
+	}
+}
+
+/// A number that doesn't correspond to any [`Key`].
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidKey(pub u8);
+
+/// A combination of two keys pressed at the same time, as recognized by [`KeyMap::combo_from_reading`].
+///
+/// See the [mapping documentation][crate::mapping#key-combinations] for why other combinations aren't included.
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combo {
+	/// `K1` and `K2` keys pressed together.
+	K1K2,
+	/// `K1` and `K3` keys pressed together. Reads the same as pressing `K1`, `K2` and `K3`.
+	K1K3,
+	/// `K2` and `K3` keys pressed together.
+	K2K3,
+}
+
+/// A change in key state, as reported by [`KC11B04::poll_event`].
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+	/// A key was pressed, while previously no key was.
+	Pressed(Key),
+	/// A key was released, and now no key is pressed.
+	Released(Key),
+	/// Went directly from one key to another.
+	Changed {
+		/// The previous key state.
+		from: Option<Key>,
+		/// The new key state.
+		to: Option<Key>,
+	},
+}
+
+impl Event {
+	/// The event for a change from one key state to another, or [`None`] when they're the same.
+	pub(crate) fn between(from: Option<Key>, to: Option<Key>) -> Option<Event> {
+		match (from, to) {
+			(from, to) if from == to => None,
+			(None, Some(to)) => Some(Event::Pressed(to)),
+			(Some(from), None) => Some(Event::Released(from)),
+			(from, to) => Some(Event::Changed { from, to }),
+		}
+	}
+}
+
+/// The key state of a single reading, as classified by [`KeyMap::key_state_from_reading`].
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyState {
+	/// No key is pressed, the reading is below the noise floor.
+	None,
+	/// A single key is pressed.
+	Pressed(Key),
+	/// The reading doesn't match any key, but is above the noise floor.
+	/// Typically multiple keys pressed at the same time.
+	Ambiguous,
+}
+
 /// [`KeyMap`] for 8bit ADCs with a maximum reading of `255`.
-pub const MAP_8BIT: KeyMap<u8> = map_from_max!(u8, 255);
+pub const MAP_8BIT: KeyMap<u8> = predefined_map!(u8, 255);
 
 /// [`KeyMap`] for 10bit ADCs with a maximum reading of `1023`.
-pub const MAP_10BIT: KeyMap<u16> = map_from_max!(u16, 1023);
+pub const MAP_10BIT: KeyMap<u16> = predefined_map!(u16, 1023);
+
+/// [`KeyMap`] for 11bit ADCs with a maximum reading of `2047`.
+pub const MAP_11BIT: KeyMap<u16> = predefined_map!(u16, 2047);
 
 /// [`KeyMap`] for 12bit ADCs with a maximum reading of `4095`.
-pub const MAP_12BIT: KeyMap<u16> = map_from_max!(u16, 4095);
+pub const MAP_12BIT: KeyMap<u16> = predefined_map!(u16, 4095);
 
 /// [`KeyMap`] for signed 12bit ADCs with a maximum *positive* reading of `2047`.
-pub const MAP_12BIT_SIGNED: KeyMap<i16> = map_from_max!(i16, 2047);
+pub const MAP_12BIT_SIGNED: KeyMap<i16> = predefined_map!(i16, 2047);
+
+/// [`KeyMap`] for 14bit ADCs with a maximum reading of `16383`.
+pub const MAP_14BIT: KeyMap<u16> = predefined_map!(u16, 16383);
 
 /// [`KeyMap`] for 16bit ADCs with a maximum reading of `65535`.
-pub const MAP_16BIT: KeyMap<u16> = map_from_max!(u16, 65535);
+pub const MAP_16BIT: KeyMap<u16> = predefined_map!(u16, 65535);
 
 /// [`KeyMap`] for signed 16bit ADCs with a maximum *positive* reading of `32767`.
-pub const MAP_16BIT_SIGNED: KeyMap<i16> = map_from_max!(i16, 32767);
+pub const MAP_16BIT_SIGNED: KeyMap<i16> = predefined_map!(i16, 32767);
 
 /// [`KeyMap`] for 24bit ADCs with a maximum reading of `16777215`.
-pub const MAP_24BIT: KeyMap<u32> = map_from_max!(u32, 0xFFFFFF);
+pub const MAP_24BIT: KeyMap<u32> = predefined_map!(u32, 0xFFFFFF);
+
+/// Picks the predefined `u16` map for an ADC resolution only known at runtime, such as from a register.
+///
+/// Supports the depths of the predefined `u16` maps: 10, 11, 12, 14 and 16 bits, as well as 8 bits,
+/// being the same map as [`MAP_8BIT`] but in a `u16`. Other depths return [`None`].
+/// The return type is fixed, so 24 bits needs [`map_for_bits_u32`] instead.
+/// For depths without a predefined map, see [`KeyMap::scaled`].
+///
+/// ```rust
+/// use kc11b04::{map_for_bits, MAP_12BIT};
+///
+/// assert_eq!(map_for_bits(12), Some(MAP_12BIT));
+/// assert_eq!(map_for_bits(9), None);
+/// ```
+pub fn map_for_bits(bits: u32) -> Option<KeyMap<u16>> {
+	match bits {
+		8 => Some(KeyMap::for_bits::<8>()),
+		10 => Some(MAP_10BIT),
+		11 => Some(MAP_11BIT),
+		12 => Some(MAP_12BIT),
+		14 => Some(MAP_14BIT),
+		16 => Some(MAP_16BIT),
+		_ => None,
+	}
+}
+
+/// Like [`map_for_bits`], but for the predefined `u32` map, [`MAP_24BIT`].
+pub fn map_for_bits_u32(bits: u32) -> Option<KeyMap<u32>> {
+	match bits {
+		24 => Some(MAP_24BIT),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	extern crate std;
+
+	use crate::{InvalidKey, Key};
+	use std::format;
+
+	#[test]
+	fn all_keys_in_order() {
+		assert_eq!(Key::all(), [Key::K1, Key::K2, Key::K3, Key::K4]);
+	}
+
+	#[test]
+	fn sort_keys() {
+		let mut keys = [Key::K3, Key::K1, Key::K4, Key::K2, Key::K1];
+		keys.sort();
+		assert_eq!(keys, [Key::K1, Key::K1, Key::K2, Key::K3, Key::K4]);
+		assert!(Key::K1 < Key::K4);
+	}
+
+	#[test]
+	fn keys_as_map_keys() {
+		let mut handlers = heapless::FnvIndexMap::<Key, char, 4>::new();
+		for key in Key::all() {
+			handlers.insert(key, char::from(key)).unwrap();
+		}
+		assert_eq!(handlers.len(), 4);
+		assert_eq!(handlers.get(&Key::K3), Some(&'3'));
+	}
+
+	#[test]
+	fn key_conversions() {
+		let keys = Key::all();
+		assert_eq!(keys.map(Key::index), [0, 1, 2, 3]);
+		assert_eq!(keys.map(char::from), ['1', '2', '3', '4']);
+		assert_eq!(
+			[1, 2, 3, 4].map(Key::try_from),
+			[Ok(Key::K1), Ok(Key::K2), Ok(Key::K3), Ok(Key::K4)]
+		);
+		assert_eq!(
+			(Key::try_from(0), Key::try_from(5)),
+			(Err(InvalidKey(0)), Err(InvalidKey(5)))
+		);
+	}
+
+	#[test]
+	fn keys_from_index() {
+		let keys = Key::all();
+		assert_eq!(keys.len(), Key::COUNT);
+		assert_eq!(keys.map(|key| Key::from_index(key.index())), keys.map(Some));
+		assert_eq!((Key::from_index(4), Key::from_index(u8::MAX)), (None, None));
+	}
+
+	#[test]
+	fn keys_from_bytes() {
+		let keys = Key::all();
+		assert_eq!(keys.map(Key::as_byte), *b"1234");
+		assert_eq!(
+			keys.map(|key| Key::from_byte(key.as_byte())),
+			keys.map(Some)
+		);
+		assert_eq!((Key::from_byte(b'0'), Key::from_byte(1)), (None, None));
+	}
+
+	#[test]
+	fn display_keys() {
+		assert_eq!(
+			Key::all().map(|key| format!("{}", key)),
+			["K1", "K2", "K3", "K4"]
+		);
+	}
+
+	#[test]
+	fn display_map_errors() {
+		use crate::MapError;
+
+		assert_eq!(
+			[
+				MapError::NonMonotonic {
+					lower: Key::K2,
+					upper: Key::K3
+				},
+				MapError::Overlap {
+					lower: Key::K1,
+					upper: Key::K2
+				},
+				MapError::MarginTooLarge,
+			]
+			.map(|err| format!("{}", err)),
+			[
+				"expected value of K3 isn't greater than K2",
+				"bands of K1 and K2 overlap",
+				"margin below K1 reaches 0",
+			]
+		);
+		assert_eq!(
+			[
+				crate::CalibError::Missing(Key::K4),
+				crate::CalibError::Invalid(MapError::MarginTooLarge),
+			]
+			.map(|err| format!("{}", err)),
+			[
+				"no reading recorded for K4",
+				"invalid map: margin below K1 reaches 0"
+			]
+		);
+		assert_eq!(
+			format!("{}", crate::NoKey(350)),
+			"no key matches reading 350"
+		);
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn std_errors() {
+		use crate::{CalibError, Error, MapError};
+		use std::{boxed::Box, error::Error as _, string::ToString};
+
+		let errors: [Box<dyn std::error::Error>; 3] = [
+			Box::new(MapError::MarginTooLarge),
+			Box::new(CalibError::Invalid(MapError::MarginTooLarge)),
+			Box::new(Error::Adc(embedded_hal_mock::MockError::Io(
+				std::io::ErrorKind::Other,
+			))),
+		];
+		assert_eq!(
+			errors.map(|err| err.to_string()),
+			[
+				"margin below K1 reaches 0",
+				"invalid map: margin below K1 reaches 0",
+				"ADC reading failed: Io(Other)",
+			]
+		);
+		assert!(CalibError::Invalid(MapError::MarginTooLarge)
+			.source()
+			.is_some());
+	}
+
+	/// A predefined map's fields, which use the same margin on both sides,
+	/// and the lower edge of the K1 band as the noise floor.
+	fn fields<Word>(k: [Word; 4], margin: Word) -> crate::KeyMap<Word>
+	where
+		Word: Copy + core::ops::Sub<Output = Word>,
+	{
+		crate::KeyMap {
+			k1: k[0],
+			k2: k[1],
+			k3: k[2],
+			k4: k[3],
+			margin,
+			margin_high: margin,
+			noise_floor: k[0] - margin,
+		}
+	}
+
+	#[cfg(not(feature = "no-float"))]
+	#[test]
+	fn predefined_map_values() {
+		use crate::*;
+
+		assert_eq!(MAP_8BIT, fields([100, 151, 202, 255], 7));
+		assert_eq!(MAP_10BIT, fields([404, 606, 811, 1023], 30));
+		assert_eq!(MAP_11BIT, fields([809, 1213, 1624, 2047], 61));
+		assert_eq!(MAP_12BIT, fields([1618, 2427, 3250, 4095], 122));
+		assert_eq!(MAP_12BIT_SIGNED, fields([809, 1213, 1624, 2047], 61));
+		assert_eq!(MAP_14BIT, fields([6475, 9713, 13002, 16383], 491));
+		assert_eq!(MAP_16BIT, fields([25903, 38854, 52011, 65535], 1966));
+		assert_eq!(MAP_16BIT_SIGNED, fields([12951, 19427, 26005, 32767], 983));
+		assert_eq!(
+			MAP_24BIT,
+			fields([6631310, 9946965, 13315250, 16777215], 503316)
+		);
+	}
+
+	#[cfg(feature = "no-float")]
+	#[test]
+	fn predefined_map_values() {
+		use crate::*;
+
+		assert_eq!(MAP_8BIT, fields([100, 151, 202, 255], 7));
+		assert_eq!(MAP_10BIT, fields([404, 606, 812, 1023], 30));
+		assert_eq!(MAP_11BIT, fields([808, 1213, 1625, 2047], 61));
+		assert_eq!(MAP_12BIT, fields([1617, 2428, 3251, 4095], 122));
+		assert_eq!(MAP_12BIT_SIGNED, fields([808, 1213, 1625, 2047], 61));
+		assert_eq!(MAP_14BIT, fields([6471, 9715, 13008, 16383], 491));
+		assert_eq!(MAP_16BIT, fields([25886, 38862, 52034, 65535], 1966));
+		assert_eq!(MAP_16BIT_SIGNED, fields([12942, 19430, 26016, 32767], 983));
+		assert_eq!(
+			MAP_24BIT,
+			fields([6626999, 9948888, 13321108, 16777215], 503316)
+		);
+	}
+
+	#[test]
+	fn maps_for_runtime_bits() {
+		use crate::*;
+
+		assert_eq!(
+			[10, 11, 12, 14, 16].map(map_for_bits),
+			[MAP_10BIT, MAP_11BIT, MAP_12BIT, MAP_14BIT, MAP_16BIT].map(Some)
+		);
+		assert_eq!(
+			map_for_bits(8).map(|map| [map.k1, map.k2, map.k3, map.k4]),
+			Some([MAP_8BIT.k1, MAP_8BIT.k2, MAP_8BIT.k3, MAP_8BIT.k4].map(u16::from))
+		);
+		assert_eq!([0, 9, 13, 24, 32].map(map_for_bits), [None; 5]);
+		assert_eq!([24, 16].map(map_for_bits_u32), [Some(MAP_24BIT), None]);
+	}
+
+	#[test]
+	fn predefined_maps_are_valid() {
+		use crate::*;
+
+		assert_eq!(
+			[
+				MAP_8BIT.validate(),
+				MAP_10BIT.validate(),
+				MAP_11BIT.validate(),
+				MAP_12BIT.validate(),
+				MAP_12BIT_SIGNED.validate(),
+				MAP_14BIT.validate(),
+				MAP_16BIT.validate(),
+				MAP_16BIT_SIGNED.validate(),
+				MAP_24BIT.validate(),
+			],
+			[Ok(()); 9]
+		);
+	}
+
+	#[cfg(feature = "defmt-0-3")]
+	#[test]
+	fn defmt_derives() {
+		fn assert_format<T: defmt::Format>() {}
+		assert_format::<Key>();
+		assert_format::<InvalidKey>();
+		assert_format::<crate::CalibError>();
+		assert_format::<crate::Error<u8>>();
+		assert_format::<crate::Combo>();
+		assert_format::<crate::Event>();
+		assert_format::<crate::LineStatus>();
+		assert_format::<crate::KeyState>();
+		assert_format::<crate::ReadingStats<u16>>();
+		assert_format::<crate::KeyRepeat>();
+		assert_format::<crate::Press>();
+		assert_format::<crate::KeyMap<u16>>();
+		assert_format::<crate::mapping::KeyMapN<8, u16>>();
+		assert_format::<crate::MapError>();
+		assert_format::<crate::NoKey<u16>>();
+		#[cfg(feature = "test-util")]
+		assert_format::<crate::test_util::Exhausted>();
+	}
+
+	#[cfg(feature = "ufmt-0-2")]
+	#[test]
+	fn ufmt_derives() {
+		fn assert_udebug<T: ufmt::uDebug>() {}
+		assert_udebug::<Key>();
+		assert_udebug::<InvalidKey>();
+		assert_udebug::<crate::CalibError>();
+		assert_udebug::<crate::Error<u8>>();
+		assert_udebug::<crate::Combo>();
+		assert_udebug::<crate::Event>();
+		assert_udebug::<crate::LineStatus>();
+		assert_udebug::<crate::KeyState>();
+		assert_udebug::<crate::ReadingStats<u16>>();
+		assert_udebug::<crate::KeyRepeat>();
+		assert_udebug::<crate::Press>();
+		assert_udebug::<crate::KeyMap<u16>>();
+		assert_udebug::<crate::mapping::KeyMapN<8, u16>>();
+		assert_udebug::<crate::MapError>();
+		assert_udebug::<crate::NoKey<u16>>();
+		#[cfg(feature = "test-util")]
+		assert_udebug::<crate::test_util::Exhausted>();
+	}
+}
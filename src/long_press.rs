@@ -0,0 +1,163 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use embedded_hal::adc::{Channel, OneShot};
+
+use crate::{driver::NbError, Classifier, Key, KeyMap, KC11B04};
+
+/// Wraps a [`KC11B04`] driver to distinguish tapping a key from holding it. Constructed with [`LongPress::new`].
+///
+/// The crate doesn't depend on any clock. Instead every poll takes the current time in ticks,
+/// from whatever monotonic timer or counter is available. The tick counter may wrap around.
+pub struct LongPress<Pin, ADC, Word, Map = KeyMap<Word>> {
+	keypad: KC11B04<Pin, ADC, Word, Map>,
+	threshold: u32,
+	pressed: Option<Held>,
+}
+
+/// A key press classified by its duration, as reported by [`LongPress::poll`].
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Press {
+	/// The key was released before reaching the threshold.
+	Tap(Key),
+	/// The key has been held for at least the threshold, and is still pressed.
+	Hold(Key),
+}
+
+struct Held {
+	key: Key,
+	since: u32,
+	reported: bool,
+}
+
+impl<Pin, ADC, Word, Map> LongPress<Pin, ADC, Word, Map>
+where
+	Pin: Channel<ADC>,
+	Word: Copy,
+	Map: Classifier<Word>,
+{
+	/// Wrap a [`KC11B04`] driver, reporting a [`Press::Hold`] once a key is held for `threshold` ticks.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, LongPress, Press, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 1023),
+	/// # ]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// // Consider a key held after 500 ticks.
+	/// let mut keypad = LongPress::new(KC11B04::new(analog_pin, MAP_10BIT), 500);
+	///
+	/// assert_eq!(keypad.poll(&mut adc, 0), Ok(None));
+	/// assert_eq!(keypad.poll(&mut adc, 200), Ok(None));
+	/// assert_eq!(keypad.poll(&mut adc, 600), Ok(Some(Press::Hold(Key::K4))));
+	/// ```
+	pub fn new(keypad: KC11B04<Pin, ADC, Word, Map>, threshold: u32) -> Self {
+		Self {
+			keypad,
+			threshold,
+			pressed: None,
+		}
+	}
+
+	/// Takes an ADC reading at time `now` in ticks, and reports a [`Press`] when one is recognized.
+	///
+	/// A [`Press::Hold`] is reported once per press, on the first poll past the threshold.
+	/// A [`Press::Tap`] is reported on the poll where the key is no longer pressed.
+	pub fn poll<Adc>(
+		&mut self,
+		adc: &mut Adc,
+		now: u32,
+	) -> Result<Option<Press>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let key = self.keypad.key_state(adc)?;
+		let press = match &mut self.pressed {
+			Some(held) if Some(held.key) == key => {
+				if !held.reported && now.wrapping_sub(held.since) >= self.threshold {
+					held.reported = true;
+					Some(Press::Hold(held.key))
+				} else {
+					None
+				}
+			}
+			pressed => {
+				let press = match pressed {
+					Some(held) if !held.reported => Some(Press::Tap(held.key)),
+					_ => None,
+				};
+				*pressed = key.map(|key| Held {
+					key,
+					since: now,
+					reported: false,
+				});
+				press
+			}
+		};
+		Ok(press)
+	}
+
+	/// Releases the wrapped [`KC11B04`] driver.
+	pub fn into_inner(self) -> KC11B04<Pin, ADC, Word, Map> {
+		self.keypad
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{Key, LongPress, Press, KC11B04, MAP_10BIT};
+	use embedded_hal::adc::Channel;
+	use embedded_hal_mock::adc::{Mock, MockChan0, Transaction};
+
+	#[test]
+	fn taps_and_holds() {
+		use MockChan0 as PIN;
+
+		let polls = [
+			// Tap K1.
+			(400, 0),
+			(400, 100),
+			(0, 200),
+			// Hold K2.
+			(600, 300),
+			(600, 700),
+			(600, 800),
+			(600, 900),
+			(0, 1000),
+			// Tap K3, directly followed by K4.
+			(800, 1100),
+			(1023, 1200),
+			(0, 1300),
+		];
+		let expected = polls.map(|(r, _)| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad = LongPress::new(KC11B04::new(PIN, MAP_10BIT), 500);
+
+		let presses = polls.map(|(_, now)| keypad.poll(&mut adc, now).unwrap());
+
+		assert_eq!(
+			presses,
+			[
+				None,
+				None,
+				Some(Press::Tap(Key::K1)),
+				None,
+				None,
+				Some(Press::Hold(Key::K2)),
+				None,
+				None,
+				None,
+				Some(Press::Tap(Key::K3)),
+				Some(Press::Tap(Key::K4)),
+			]
+		);
+	}
+}
@@ -26,14 +26,18 @@
 //! 	k3: 812,
 //! 	k4: 1023,
 //! 	margin: 30,
+//! 	margin_high: 30,
+//! 	noise_floor: 374,
 //! };
 //! ```
 //!
 //! Or you could perform all floating point math in a constant expression.
 //! Letting the compiler do this math *at compile time*, keeping the binary small and
 //! avoiding the performance hit on your embedded device.
+//! Not available with the `no-float` feature.
 //!
-//! ```rust
+#![cfg_attr(not(feature = "no-float"), doc = "```rust")]
+#![cfg_attr(feature = "no-float", doc = "```ignore")]
 //! use kc11b04::KeyMap;
 //!
 //! // Floating point math is supported in constant definitions.
@@ -48,19 +52,58 @@
 //! 		k3: (max as f32 * K3_F) as u16,
 //! 		k4: max,
 //! 		margin: (max as f32 * margin) as u16,
+//! 		margin_high: (max as f32 * margin) as u16,
+//! 		noise_floor: (max as f32 * K1_F) as u16 - (max as f32 * margin) as u16,
 //! 	}
 //! };
 //! ```
 //!
 //! This is effectively what the [`map_from_max!`][crate::map_from_max] macro allows you to do as well.
 //!
-//! ```rust
+#![cfg_attr(not(feature = "no-float"), doc = "```rust")]
+#![cfg_attr(feature = "no-float", doc = "```ignore")]
 //! use kc11b04::{KeyMap, map_from_max};
 //!
 //! /// 10bit map, but with 15% margin.
 //! const CUSTOM_MAP: KeyMap<u16> = map_from_max!(u16, 1023, 0.15);
 //! ```
 //!
+//! To avoid floating point math altogether, [`KeyMap::from_max_permille`] uses only integer math instead.
+//!
+//! ```rust
+//! use kc11b04::KeyMap;
+//! use kc11b04::mapping::{K1_PM, K2_PM, K3_PM};
+//!
+//! /// 10bit map, but with 15% margin.
+//! const CUSTOM_MAP: KeyMap<u16> = KeyMap::from_max_permille(1023, K1_PM, K2_PM, K3_PM, 150);
+//! ```
+//!
+//! ## Other resolutions
+//!
+//! The predefined maps cover common ADCs, but any max reading works with the macro or the `const fn`s.
+//! For an `N`-bit ADC the max reading is `(1 << N) - 1`, which [`KeyMap::for_bits`] uses directly.
+//!
+//! | Bits | Max     | Map                                   |
+//! | ---- | ------- | ------------------------------------- |
+//! | 8    | `255`   | [`MAP_8BIT`][crate::MAP_8BIT]         |
+//! | 9    | `511`   | `KeyMap::for_bits::<9>()`             |
+//! | 10   | `1023`  | [`MAP_10BIT`][crate::MAP_10BIT]       |
+//! | 11   | `2047`  | [`MAP_11BIT`][crate::MAP_11BIT]       |
+//! | 12   | `4095`  | [`MAP_12BIT`][crate::MAP_12BIT]       |
+//! | 13   | `8191`  | `KeyMap::for_bits::<13>()`            |
+//! | 14   | `16383` | [`MAP_14BIT`][crate::MAP_14BIT]       |
+//! | 16   | `65535` | [`MAP_16BIT`][crate::MAP_16BIT]       |
+//!
+//! ```rust
+//! use kc11b04::{KeyMap, map_from_max_permille};
+//!
+//! /// 9bit map, with the default 3% margin.
+//! const MAP_9BIT: KeyMap<u16> = KeyMap::for_bits::<9>();
+//!
+//! /// 13bit map, but with 5% margin.
+//! const MAP_13BIT: KeyMap<u16> = map_from_max_permille!(u16, 8191, 50);
+//! ```
+//!
 //! ## Schematic and factors
 //!
 //! While the manufacturer provides a table of voltages, it's actually off slightly.
@@ -102,6 +145,34 @@
 //! | K3   | [`K3_F`] | ~79.4%        | 80%         |
 //! | K4   | -        | 100%          | 100%        |
 //!
+//! ## Key combinations
+//!
+//! Pressing multiple keys connects more than one point of the divider to `AD`,
+//! shorting out the resistors in between. Some of these produce a predictable voltage of their own,
+//! which [`KeyMap::combo_from_reading`] can recognize.
+//!
+//! - K1 + K2 shorts `R2`, giving `~1961 / (2000 + ~1961) = ~0.495`.
+//! - K2 + K3 shorts `R3`, giving `~2913 / (1000 + ~2913) = ~0.744`.
+//! - K1 + K3 shorts both `R2` and `R3`, giving `~1961 / (1000 + ~1961) = ~0.662`.
+//!
+//! Other combinations are ambiguous:
+//!
+//! - K1 + K2 + K3 shorts the same resistors as K1 + K3, so they read the same.
+//! - Any combination with K4 connects `AD` to `VCC` directly, reading the same as K4 alone.
+//!
+//! | Keys    | Constant    | Percent |
+//! | ------- | ----------- | ------- |
+//! | K1 + K2 | [`K1K2_PM`] | ~49.5%  |
+//! | K1 + K3 | [`K1K3_PM`] | ~66.2%  |
+//! | K2 + K3 | [`K2K3_PM`] | ~74.4%  |
+//!
+//! ## Band bounds
+//!
+//! Each key matches readings in an inclusive band around its expected value,
+//! `[expected - margin, expected + margin_high]`.
+//! The exception is K4, being the max reading its band is open-ended: `[k4 - margin, ..]`.
+//! Key combinations use the same bounds, and [`KeyMap::validate`] reports bands that share any reading.
+//!
 #![cfg_attr(
 	feature = "doc-images",
 	doc = ::embed_doc_image::embed_image!("kc11b04-schema", "docs/KC11B04-schema.svg")
@@ -113,16 +184,42 @@
 
 use core::ops::{Add, Sub};
 
-use crate::Key;
+use crate::{Combo, Event, Key, KeyState};
 
 /// Maps keys to their expected ADC readings.
 ///
 /// This depends on the ADC resolution, so you may want to use a constant for this such as [`MAP_10BIT`][crate::MAP_10BIT].
 ///
 /// For creating a custom map, see the module documentation [`kc11b04::mapping`][crate::mapping].
+///
+/// A map is plain data and is [`Sync`] whenever `Word` is, so it can live in a `static`
+/// shared between an interrupt handler and the main loop, without any locking.
+///
+/// ```rust
+/// use kc11b04::{Key, KeyMap, MAP_10BIT};
+/// # use embedded_hal::adc::OneShot;
+/// # use embedded_hal_mock::adc::*;
+///
+/// static MAP: KeyMap<u16> = MAP_10BIT;
+///
+/// // For example from an ADC conversion complete interrupt, forwarding keys to the main loop.
+/// fn on_adc_interrupt(reading: u16, send: &mut impl FnMut(Key)) {
+/// 	if let Some(key) = MAP.key_from_reading(reading) {
+/// 		send(key);
+/// 	}
+/// }
+/// #
+/// # let mut adc = Mock::new(&[Transaction::read(0, 812)]);
+/// # let reading: u16 = adc.read(&mut MockChan0).unwrap();
+///
+/// let mut received = None;
+/// on_adc_interrupt(reading, &mut |key| received = Some(key));
+/// assert_eq!(received, Some(Key::K3));
+/// ```
 #[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
 #[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
-#[derive(Debug)]
+#[cfg_attr(feature = "serde-1", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct KeyMap<Word> {
 	/// The expected ADC reading for K1, before margins.
 	///
@@ -144,107 +241,1311 @@ pub struct KeyMap<Word> {
 	/// For predefined maps it's equal to the max reading of the ADC.
 	pub k4: Word,
 
-	/// The absolute margin a reading may deviate from the above expected values.
+	/// The absolute margin a reading may be below the above expected values.
+	/// The bound is inclusive, so a margin of `0` still matches a reading exactly on the expected value.
 	/// The default is `3%` of the max reading of the ADC.
+	///
+	/// Note this is an absolute value in ADC readings, not a percentage or factor like the argument of
+	/// [`map_from_max!`][crate::map_from_max]. To set it from a percentage, use [`KeyMap::set_margin_percent`].
 	pub margin: Word,
+
+	/// The absolute margin a reading may be above the expected values.
+	/// Not used for K4, as its band is open-ended.
+	///
+	/// The default is the same as [`margin`][KeyMap::margin].
+	/// Because the divider isn't linear, a different margin on either side can fit a module better,
+	/// see [`KeyMap::with_margins`].
+	pub margin_high: Word,
+
+	/// Readings below this are considered idle noise, rather than an unrecognized key combination.
+	/// The bound is exclusive, a reading equal to the noise floor is not idle.
+	/// Only [`KeyMap::key_state_from_reading`] and the driver methods built on it use this,
+	/// [`KeyMap::key_from_reading`] reports no key for idle and unrecognized readings alike.
+	///
+	/// The default is the lower edge of the K1 band, `k1 - margin`.
+	/// That makes every reading below the K1 band idle, so [`KeyState::Ambiguous`] is only reported
+	/// for readings in the gaps between and above the bands.
+	/// To tell a genuinely idle keypad apart from readings in the gap below K1,
+	/// log [`KC11B04::read_raw`][crate::KC11B04::read_raw] without pressing any key,
+	/// and use [`KeyMap::with_noise_floor`] to set this a little above the highest idle reading you observe.
+	pub noise_floor: Word,
+}
+
+impl<Word> KeyMap<Word>
+where
+	Word: Copy,
+{
+	/// Returns the map with a different absolute margin on both sides, keeping the expected values.
+	///
+	/// ```rust
+	/// use kc11b04::MAP_10BIT;
+	///
+	/// // Widen the margin to ~8% for a noisy ADC.
+	/// let noisy_map = MAP_10BIT.with_margin(81);
+	/// ```
+	pub fn with_margin(self, margin: Word) -> Self {
+		KeyMap {
+			margin,
+			margin_high: margin,
+			..self
+		}
+	}
+
+	/// Returns the map with separate absolute margins below and above the expected values.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// // This module reads a little high, so allow more room above each key.
+	/// let map = MAP_10BIT.with_margins(20, 50);
+	/// assert_eq!(map.key_from_reading(650), Some(Key::K2));
+	/// assert_eq!(map.key_from_reading(380), None);
+	/// ```
+	pub fn with_margins(self, low: Word, high: Word) -> Self {
+		KeyMap {
+			margin: low,
+			margin_high: high,
+			..self
+		}
+	}
+
+	/// Returns the map with a different [`noise_floor`][KeyMap::noise_floor], keeping the bands.
+	///
+	/// ```rust
+	/// use kc11b04::{KeyState, MAP_10BIT};
+	///
+	/// // Idle readings of this module stay below 40.
+	/// let map = MAP_10BIT.with_noise_floor(40);
+	/// assert_eq!(map.key_state_from_reading(12), KeyState::None);
+	/// assert_eq!(map.key_state_from_reading(250), KeyState::Ambiguous);
+	/// ```
+	pub fn with_noise_floor(self, noise_floor: Word) -> Self {
+		KeyMap {
+			noise_floor,
+			..self
+		}
+	}
+
+	/// Changes the absolute margin on both sides, keeping the expected values.
+	pub fn set_margin(&mut self, margin: Word) {
+		self.margin = margin;
+		self.margin_high = margin;
+	}
+
+	/// The expected ADC reading for the given key, before margins.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// // Expected readings for each key, to show in a calibration table.
+	/// let table = Key::all().map(|key| MAP_10BIT.expected(key));
+	/// assert_eq!(table, [MAP_10BIT.k1, MAP_10BIT.k2, MAP_10BIT.k3, MAP_10BIT.k4]);
+	/// ```
+	pub fn expected(&self, key: Key) -> Word {
+		match key {
+			Key::K1 => self.k1,
+			Key::K2 => self.k2,
+			Key::K3 => self.k3,
+			Key::K4 => self.k4,
+		}
+	}
+
+	/// Returns the map with a different expected value for `key`, keeping everything else.
+	///
+	/// Useful when a single resistor is off-tolerance. Note the [`noise_floor`][KeyMap::noise_floor]
+	/// stays the same, also when changing K1.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// // This module reads K3 a little low.
+	/// let map = MAP_10BIT.with_key(Key::K3, 790);
+	/// ```
+	pub fn with_key(self, key: Key, value: Word) -> Self {
+		let mut map = self;
+		match key {
+			Key::K1 => map.k1 = value,
+			Key::K2 => map.k2 = value,
+			Key::K3 => map.k3 = value,
+			Key::K4 => map.k4 = value,
+		}
+		map
+	}
+
+	/// Whether every value of the map fits in the word type `W`, such as to check a map before converting it.
+	///
+	/// The driver and its map must use the same `Word`, so the compiler already refuses to pair
+	/// [`MAP_16BIT`][crate::MAP_16BIT] with a `u8` ADC directly. The pitfall is converting readings or maps
+	/// between word types along the way, where values above the max of `W` would be truncated or rejected.
+	/// Also note a map made for a higher resolution than the ADC has keeps K4 out of reach, even if the word type matches.
+	///
+	/// ```rust
+	/// use kc11b04::{MAP_10BIT, MAP_8BIT};
+	///
+	/// assert!(!MAP_10BIT.fits::<u8>());
+	/// assert!(MAP_8BIT.fits::<u8>() && MAP_8BIT.fits::<i16>());
+	/// ```
+	pub fn fits<W>(&self) -> bool
+	where
+		W: TryFrom<Word>,
+	{
+		[
+			self.k1,
+			self.k2,
+			self.k3,
+			self.k4,
+			self.margin,
+			self.margin_high,
+			self.noise_floor,
+		]
+		.into_iter()
+		.all(|val| W::try_from(val).is_ok())
+	}
+
+	/// Returns the map shifted by `baseline`, the reading of an idle keypad, for ADCs that don't read `0` when idle.
+	///
+	/// Predefined maps assume an idle reading of `0`. With an offset, such as a signed ADC idling slightly negative,
+	/// the expected values and [`noise_floor`][KeyMap::noise_floor] are moved along with the baseline,
+	/// so the no-key region is centered on it. The margins stay the same.
+	/// Returns [`None`] if any of the shifted values doesn't fit in `Word`, such as a calibrated map near its max,
+	/// or when the shifted map fails [`KeyMap::validate`], such as a baseline pushing K1 below its margin.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_12BIT_SIGNED};
+	///
+	/// // This ADC reads -10 when idle.
+	/// let map = MAP_12BIT_SIGNED.with_idle_baseline(-10).unwrap();
+	/// assert_eq!(map.key_from_reading(-10), None);
+	/// assert_eq!(map.key_from_reading(MAP_12BIT_SIGNED.k1 - 10), Some(Key::K1));
+	/// assert_eq!(MAP_12BIT_SIGNED.with_idle_baseline(-1000), None);
+	/// ```
+	pub fn with_idle_baseline(self, baseline: Word) -> Option<Self>
+	where
+		Word: Sub<Output = Word> + Ord + Into<i64> + TryFrom<i64>,
+	{
+		let shift = |val: Word| {
+			let shifted = val.into().checked_add(baseline.into())?;
+			Word::try_from(shifted).ok()
+		};
+		let map = KeyMap {
+			k1: shift(self.k1)?,
+			k2: shift(self.k2)?,
+			k3: shift(self.k3)?,
+			k4: shift(self.k4)?,
+			noise_floor: shift(self.noise_floor)?,
+			..self
+		};
+		map.validate().ok()?;
+		Some(map)
+	}
 }
 
 impl<Word> KeyMap<Word>
 where
-	Word: Copy + Add<Output = Word> + Sub<Output = Word> + Ord,
+	Word: Copy + Add<Output = Word> + Sub<Output = Word> + Ord + TryFrom<i64>,
 {
 	/// Takes an ADC reading and finds whether it's in the expected range of a key.
 	///
 	/// Will be [`None`] when no key is pressed, but also for some simultaneous key combinations.
+	///
+	/// Readings below the K1 band return early, as an idle keypad is the most common case to classify.
+	///
+	/// The K4 band has no upper bound, so readings above the max of the ADC, such as from reference noise,
+	/// still match K4.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// assert_eq!(MAP_10BIT.key_from_reading(1100), Some(Key::K4));
+	/// ```
 	pub fn key_from_reading(&self, val: Word) -> Option<Key> {
-		match val {
-			v if v > self.k1 - self.margin && v < self.k1 + self.margin => Some(Key::K1),
-			v if v > self.k2 - self.margin && v < self.k2 + self.margin => Some(Key::K2),
-			v if v > self.k3 - self.margin && v < self.k3 + self.margin => Some(Key::K3),
-			v if v > self.k4 - self.margin => Some(Key::K4),
-			_ => None,
+		// Fast path for the common idle case, readings below the K1 band can't match any key.
+		if val < self.k1 && !within_below(val, self.k1, self.margin) {
+			return None;
 		}
+		Key::all().into_iter().find(|&key| {
+			let expected = self.expected(key);
+			(key == Key::K4 && val >= expected) || self.in_band(val, expected)
+		})
 	}
-}
 
-/// Pull-down resistor value 100K ohms.
-const R_DOWN: f32 = 100_000.0;
+	/// Like [`KeyMap::key_from_reading`], but returns a [`Result`], so a reading without a key can be passed on with `?`.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, NoKey, MAP_10BIT};
+	///
+	/// assert_eq!(MAP_10BIT.try_key_from_reading(404), Ok(Key::K1));
+	/// assert_eq!(MAP_10BIT.try_key_from_reading(350), Err(NoKey(350)));
+	/// ```
+	pub fn try_key_from_reading(&self, val: Word) -> Result<Key, NoKey<Word>> {
+		self.key_from_reading(val).ok_or(NoKey(val))
+	}
 
-macro_rules! make_factor {
-	($r1:literal, $r_rest:literal, $r_down:ident) => {{
-		let r2 = 1.0 / ((1.0 / $r_rest) + (1.0 / $r_down));
-		r2 / ($r1 + r2)
-	}};
-}
+	/// Like [`KeyMap::key_from_reading`], but tells an idle keypad apart from an unrecognized reading.
+	///
+	/// Readings outside of every key's band are [`KeyState::Ambiguous`] when they're at or above the
+	/// [`noise_floor`][KeyMap::noise_floor], which is typical for multiple keys pressed at the same time.
+	/// Readings below the noise floor are always [`KeyState::None`], without checking any band.
+	/// This idle fast path is specific to this method, [`KeyMap::key_from_reading`] and
+	/// [`KeyMap::try_key_from_reading`] don't use the noise floor, and report no key for both cases.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, KeyState, MAP_10BIT};
+	///
+	/// assert_eq!(MAP_10BIT.key_state_from_reading(0), KeyState::None);
+	/// assert_eq!(MAP_10BIT.key_state_from_reading(404), KeyState::Pressed(Key::K1));
+	/// assert_eq!(MAP_10BIT.key_state_from_reading(500), KeyState::Ambiguous);
+	/// ```
+	pub fn key_state_from_reading(&self, val: Word) -> KeyState {
+		if val < self.noise_floor {
+			return KeyState::None;
+		}
+		match self.key_from_reading(val) {
+			Some(key) => KeyState::Pressed(key),
+			None => KeyState::Ambiguous,
+		}
+	}
 
-/// Relative factor for K1 button, `~39.5%` of the ADC's max reading.
-///
-/// See the module documentation [`kc11b04::mapping`][crate::mapping] for details.
-pub const K1_F: f32 = make_factor!(3000.0, 2000.0, R_DOWN);
+	/// Classifies a buffer of readings at once, like calling [`KeyMap::key_from_reading`] for each of them.
+	///
+	/// Writes the key state of `readings[i]` to `out[i]`. When the lengths differ, only the shorter length is classified.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// // A captured trace of ADC readings.
+	/// let trace = [0u16, 400, 401, 0, 1023];
+	/// let mut keys = [None; 5];
+	/// MAP_10BIT.classify_slice(&trace, &mut keys);
+	///
+	/// assert_eq!(keys, [None, Some(Key::K1), Some(Key::K1), None, Some(Key::K4)]);
+	/// ```
+	pub fn classify_slice(&self, readings: &[Word], out: &mut [Option<Key>]) {
+		for (val, key) in readings.iter().zip(out) {
+			*key = self.key_from_reading(*val);
+		}
+	}
 
-/// Relative factor for K2 button, `~59.3%` of the ADC's max reading.
-///
-/// See the module documentation [`kc11b04::mapping`][crate::mapping] for details.
-pub const K2_F: f32 = make_factor!(2000.0, 3000.0, R_DOWN);
+	/// Turns a stream of readings into key [`Event`]s, starting from no key pressed.
+	///
+	/// Like [`Debounced`][crate::Debounced], a new key state has to be read `samples` times in a row before it's reported.
+	/// Use `1` to report every change.
+	///
+	/// ```rust
+	/// use kc11b04::{Event, Key, MAP_10BIT};
+	///
+	/// // A ring buffer of readings, where K1 bounces on release.
+	/// let readings = [0u16, 404, 404, 0, 404, 0, 0];
+	/// let mut events = MAP_10BIT.events(readings, 2);
+	///
+	/// assert_eq!(events.next(), Some(Event::Pressed(Key::K1)));
+	/// assert_eq!(events.next(), Some(Event::Released(Key::K1)));
+	/// assert_eq!(events.next(), None);
+	/// ```
+	pub fn events<'a, I>(&'a self, readings: I, samples: usize) -> impl Iterator<Item = Event> + 'a
+	where
+		I: IntoIterator<Item = Word>,
+		I::IntoIter: 'a,
+	{
+		let mut stable = None;
+		let mut candidate = None;
+		let mut count = 0;
+		readings.into_iter().filter_map(move |val| {
+			let key = self.key_from_reading(val);
+			if key == stable {
+				count = 0;
+				return None;
+			}
+			if key == candidate {
+				count += 1;
+			} else {
+				candidate = key;
+				count = 1;
+			}
+			if count < samples {
+				return None;
+			}
+			count = 0;
+			Event::between(core::mem::replace(&mut stable, key), key)
+		})
+	}
 
-/// Relative factor for K3 button, `~79.4%` of the ADC's max reading.
-///
-/// See the module documentation [`kc11b04::mapping`][crate::mapping] for details.
-pub const K3_F: f32 = make_factor!(1000.0, 4000.0, R_DOWN);
+	/// The most common key state across a buffer of readings, such as a window captured by DMA.
+	///
+	/// Readings without a key count as a vote for [`None`], so a mostly idle buffer gives [`None`].
+	/// On a tie, no key wins over any key, and lower keys win over higher ones.
+	/// To turn captures into events, reduce each completed buffer and compare with the previous result.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// // With double-buffering, one half is filled by DMA while the other is processed.
+	/// let halves = [[0u16, 0, 0, 607], [607, 607, 0, 607], [607, 612, 607, 0]];
+	///
+	/// let mut last = None;
+	/// let mut changes = 0;
+	/// for half in &halves {
+	/// 	let key = MAP_10BIT.reduce_buffer(half);
+	/// 	if key != last {
+	/// 		// Handle the new key state here, once per change.
+	/// 		changes += 1;
+	/// 		last = key;
+	/// 	}
+	/// }
+	///
+	/// assert_eq!((last, changes), (Some(Key::K2), 1));
+	/// ```
+	pub fn reduce_buffer(&self, readings: &[Word]) -> Option<Key> {
+		// One slot for no key, followed by K1 to K4.
+		let mut votes = [0usize; Key::COUNT + 1];
+		for &val in readings {
+			votes[self
+				.key_from_reading(val)
+				.map_or(0, |k| k.index() as usize + 1)] += 1;
+		}
+		let mut winner = 0;
+		for (slot, &count) in votes.iter().enumerate() {
+			if count > votes[winner] {
+				winner = slot;
+			}
+		}
+		Key::from_index((winner as u8).wrapping_sub(1))
+	}
 
-/// Defines a [`KeyMap`] based on the max reading of the ADC and optional margin factor.
-///
-/// The margin defaults to `0.03` (3% of the max parameter) if omitted.
-///
-/// ## Integer type
-///
-/// The macro and [`KeyMap`] take a `Word` generic parameter to support a variety of ADCs.
+	/// Like [`KeyMap::key_from_reading`], but checks the open-ended K4 band first.
+	///
+	/// Classifying takes up to four band checks, from K1 upwards, so K4 is the slowest key to find.
+	/// When K4 is pressed far more often than the other keys, checking it first saves those comparisons.
+	/// For maps that pass [`KeyMap::validate`] the result is the same as [`KeyMap::key_from_reading`].
+	pub fn key_from_reading_k4_first(&self, val: Word) -> Option<Key> {
+		if val >= self.k4 || self.in_band(val, self.k4) {
+			return Some(Key::K4);
+		}
+		[Key::K1, Key::K2, Key::K3]
+			.into_iter()
+			.find(|&key| self.in_band(val, self.expected(key)))
+	}
+
+	/// Like [`KeyMap::key_from_reading`], but also returns how far the reading was from the key's expected value.
+	///
+	/// The distance is `0` for a reading exactly on the expected value, and at most the margin on the edges of the band.
+	/// For K4 readings above the expected value it keeps growing, as that band is open-ended.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// assert_eq!(MAP_10BIT.key_from_reading_scored(MAP_10BIT.k2), Some((Key::K2, 0)));
+	/// assert_eq!(MAP_10BIT.key_from_reading_scored(MAP_10BIT.k2 - 20), Some((Key::K2, 20)));
+	/// ```
+	pub fn key_from_reading_scored(&self, val: Word) -> Option<(Key, Word)> {
+		let key = self.key_from_reading(val)?;
+		Some((key, distance(val, self.expected(key))))
+	}
+
+	/// Whether `val` is within the margins around `expected`.
+	fn in_band(&self, val: Word, expected: Word) -> bool {
+		if val >= expected {
+			within_above(val, expected, self.margin_high)
+		} else {
+			within_below(val, expected, self.margin)
+		}
+	}
+}
+
+/// Classifies ADC readings as keys, for the [`KC11B04`][crate::KC11B04] driver and the wrappers around it.
 ///
-/// Keep in mind though this macro always maps the positive range, even when signed integers are used.
-/// As negative voltages are out-of-spec for the KC11B04 module.
+/// Implemented by [`KeyMap`]. Implement it yourself for resistor ladders with non-standard ratios,
+/// while still using the driver's events, debouncing and repeats.
 ///
 /// ```rust
-/// # use kc11b04::{KeyMap, map_from_max};
-/// /// Signed 11bit map. Meaning 10 bits of resolution in the positive voltage range,
-/// /// so the max `1023` is the same as the 10 bit map.
-/// const SIGNED_11BIT: KeyMap<i16> = map_from_max!(i16, 1023);
+/// # use embedded_hal_mock::adc::*;
+/// use kc11b04::{Classifier, Key, KC11B04};
 ///
-/// /// 24bit map needs a u32 to fit.
-/// const UNSIGNED_24BIT: KeyMap<u32> = map_from_max!(u32, 0xFFFFFF);
-/// ```
-///
-/// ## Performance note
+/// // A ladder of 2 keys, on a 10-bit ADC.
+/// struct TwoKeys;
 ///
-/// This macro will use floating point math. It's strongly recommended to
-/// **declare the result as a constant**, to avoid this FP math at runtime.
+/// impl Classifier<u16> for TwoKeys {
+/// 	fn classify(&self, val: u16) -> Option<Key> {
+/// 		match val {
+/// 			300..=700 => Some(Key::K1),
+/// 			900..=1023 => Some(Key::K2),
+/// 			_ => None,
+/// 		}
+/// 	}
+/// }
 ///
-/// ```rust
-/// # use kc11b04::{KeyMap, map_from_max};
-/// /// 10bit map, but with 15% margin.
-/// const CUSTOM_MAP: KeyMap<u16> = map_from_max!(u16, 1023, 0.15);
+/// # let mut adc = Mock::new(&[Transaction::read(0, 512)]);
+/// # let analog_pin = MockChan0;
+/// let mut keypad = KC11B04::new(analog_pin, TwoKeys);
+/// assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K1)));
 /// ```
-#[macro_export]
-macro_rules! map_from_max {
-	($word:ident, $max:literal) => {
-		map_from_max!($word, $max, 0.03)
-	};
-	($word:ident, $max:literal, $margin:literal) => {
+pub trait Classifier<Word> {
+	/// Takes an ADC reading and finds which key is pressed, if any.
+	fn classify(&self, val: Word) -> Option<Key>;
+}
+
+/// Classifies with [`KeyMap::key_from_reading`].
+impl<Word> Classifier<Word> for KeyMap<Word>
+where
+	Word: Copy + Add<Output = Word> + Sub<Output = Word> + Ord + TryFrom<i64>,
+{
+	fn classify(&self, val: Word) -> Option<Key> {
+		self.key_from_reading(val)
+	}
+}
+
+impl<Word> KeyMap<Word>
+where
+	Word: Copy + Sub<Output = Word> + Ord,
+{
+	/// Finds the key with the expected value nearest to the reading, ignoring the margins.
+	///
+	/// Unlike the strict [`KeyMap::key_from_reading`], this always picks a key, even for an idle keypad.
+	/// Useful for forgiving UIs, once you know *some* key is pressed. Ties go to the lower key.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// // Between the K1 and K2 bands, but closer to K2.
+	/// assert_eq!(MAP_10BIT.key_from_reading(550), None);
+	/// assert_eq!(MAP_10BIT.closest_key(550), Key::K2);
+	/// ```
+	pub fn closest_key(&self, val: Word) -> Key {
+		let mut closest = Key::K1;
+		for key in [Key::K2, Key::K3, Key::K4] {
+			let (lower, upper) = (self.expected(closest), self.expected(key));
+			if val <= lower {
+				break;
+			}
+			// Only subtracts from larger values, as `lower < val`.
+			if val >= upper || upper - val < val - lower {
+				closest = key;
+			} else {
+				break;
+			}
+		}
+		closest
+	}
+
+	/// Creates a validated [`KeyMap`] from the expected readings of K1 to K4, with the same `margin` on both sides.
+	///
+	/// Skips the factor math entirely, for hardware with a different resistor network than the KC11B04.
+	/// The [`noise_floor`][KeyMap::noise_floor] is set to the lower edge of the K1 band.
+	/// Returns the error of [`KeyMap::validate`] if the map isn't usable.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, KeyMap};
+	///
+	/// let map = KeyMap::from_centers([200u16, 450, 700, 1023], 50).unwrap();
+	/// assert_eq!(map.key_from_reading(460), Some(Key::K2));
+	/// assert_eq!(map.noise_floor, 150);
+	/// ```
+	pub fn from_centers(centers: [Word; 4], margin: Word) -> Result<Self, MapError> {
+		let [k1, k2, k3, k4] = centers;
+		let mut map = KeyMap {
+			k1,
+			k2,
+			k3,
+			k4,
+			margin,
+			margin_high: margin,
+			noise_floor: k1,
+		};
+		map.validate()?;
+		// Can't underflow, as validation ensures `margin < k1`.
+		map.noise_floor = k1 - margin;
+		Ok(map)
+	}
+
+	/// Like [`KeyMap::from_centers`], but sorts the expected readings first, so they don't need to be in order.
+	///
+	/// Note this assigns the lowest reading to K1 and the highest to K4, regardless of which physical key
+	/// each reading was measured on. Any key that was out of order is renamed to match the voltage.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, KeyMap};
+	///
+	/// // Accidentally swapped K2 and K3.
+	/// let map = KeyMap::from_centers_sorted([200u16, 700, 450, 1023], 50).unwrap();
+	/// assert_eq!((map.k2, map.k3), (450, 700));
+	/// assert_eq!(map.key_from_reading(700), Some(Key::K3));
+	/// ```
+	pub fn from_centers_sorted(mut centers: [Word; 4], margin: Word) -> Result<Self, MapError> {
+		centers.sort_unstable();
+		Self::from_centers(centers, margin)
+	}
+
+	/// The smallest distance between the expected values of adjacent keys.
+	///
+	/// Bands with a margin on both sides collide once the margins add up to this gap,
+	/// so a margin below `min_gap / 2` keeps them apart. Useful to pick a margin while calibrating.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// // Take the widest margin that doesn't collide, then back off a bit for safety.
+	/// let margin = MAP_10BIT.min_gap() / 2 * 3 / 4;
+	/// let map = MAP_10BIT.with_margin(margin);
+	/// assert_eq!(map.validate(), Ok(()));
+	/// assert_eq!(map.key_from_reading(map.k2 + margin), Some(Key::K2));
+	/// ```
+	pub fn min_gap(&self) -> Word {
+		let gap = |lower: Word, upper: Word| {
+			if upper >= lower {
+				upper - lower
+			} else {
+				lower - upper
+			}
+		};
+		gap(self.k1, self.k2)
+			.min(gap(self.k2, self.k3))
+			.min(gap(self.k3, self.k4))
+	}
+
+	/// Checks the expected values increase from K1 to K4, that adjacent key bands don't overlap,
+	/// and that the K1 band doesn't reach down to an idle reading of `0`.
+	///
+	/// When bands do overlap, [`KeyMap::key_from_reading`] prefers the lower key,
+	/// making part or all of the upper key's band unreachable.
+	/// As K4's band is open-ended upward, only its lower edge `k4 - margin` is checked against K3's upper edge.
+	/// Overlapping there gives readings just below K4 to K3, reported as an overlap of K3 and K4.
+	///
+	/// Because `Word` is generic this can't be a `const fn`, but it's a good fit for a unit test.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, KeyMap, MapError};
+	///
+	/// // The 10-bit KeyMap from the manufacturer's example table, but with a 25% margin.
+	/// let map = KeyMap {
+	/// 	k1: 404,
+	/// 	k2: 607,
+	/// 	k3: 812,
+	/// 	k4: 1023,
+	/// 	margin: 255,
+	/// 	margin_high: 255,
+	/// 	noise_floor: 149,
+	/// };
+	///
+	/// assert_eq!(
+	/// 	map.validate(),
+	/// 	Err(MapError::Overlap {
+	/// 		lower: Key::K1,
+	/// 		upper: Key::K2
+	/// 	})
+	/// );
+	/// ```
+	pub fn validate(&self) -> Result<(), MapError> {
+		let pairs = [
+			(Key::K1, self.k1, Key::K2, self.k2),
+			(Key::K2, self.k2, Key::K3, self.k3),
+			(Key::K3, self.k3, Key::K4, self.k4),
+		];
+		for (lower, lower_val, upper, upper_val) in pairs {
+			if upper_val <= lower_val {
+				return Err(MapError::NonMonotonic { lower, upper });
+			}
+			// Same as `gap <= margin_high + margin`, without overflowing.
+			let gap = upper_val - lower_val;
+			if gap <= self.margin_high || gap - self.margin_high <= self.margin {
+				return Err(MapError::Overlap { lower, upper });
+			}
+		}
+		if self.margin >= self.k1 {
+			return Err(MapError::MarginTooLarge);
+		}
+		Ok(())
+	}
+}
+
+impl<Word> KeyMap<Word>
+where
+	Word: Copy + Into<i64> + TryFrom<i64>,
+{
+	/// Creates a [`KeyMap`] based on the max reading of the ADC, with the default 3% margin.
+	///
+	/// Like [`KeyMap::from_max_permille`] it uses only integer math, but works at runtime for any `Word` that fits in an `i64`.
+	/// Useful for resolutions without a predefined map, or when the max is only known at runtime.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, KeyMap};
+	///
+	/// // 14bit ADC.
+	/// let map: KeyMap<u16> = KeyMap::scaled(16383);
+	/// assert_eq!(map.key_from_reading(16383), Some(Key::K4));
+	/// ```
+	pub fn scaled(max: Word) -> Self {
+		let (k1, margin) = (permille_of(max, K1_PM), permille_of(max, 30));
 		KeyMap {
-			k1: ($max as f32 * $crate::mapping::K1_F) as $word,
-			k2: ($max as f32 * $crate::mapping::K2_F) as $word,
-			k3: ($max as f32 * $crate::mapping::K3_F) as $word,
-			k4: $max,
-			margin: ($max as f32 * $margin) as $word,
+			k1,
+			k2: permille_of(max, K2_PM),
+			k3: permille_of(max, K3_PM),
+			k4: max,
+			margin,
+			margin_high: margin,
+			// The lower edge of the K1 band, which is at least `0` for a non-negative max.
+			noise_floor: Word::try_from(k1.into() - margin.into()).unwrap_or_else(|_| zero()),
 		}
-	};
+	}
+
+	/// Like [`KeyMap::from_centers`], but picks the margin for you: 40% of the [`min_gap`][KeyMap::min_gap].
+	///
+	/// With both margins adding up to 80% of the smallest gap, adjacent bands never overlap.
+	/// It can still fail validation for expected values that don't increase from K1 to K4,
+	/// or when K1 is too close to `0` for its margin.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, KeyMap};
+	///
+	/// let map = KeyMap::from_centers_auto([200u16, 450, 700, 1023]).unwrap();
+	/// assert_eq!(map.margin, 100);
+	/// assert_eq!(map.key_from_reading(540), Some(Key::K2));
+	/// ```
+	pub fn from_centers_auto(centers: [Word; 4]) -> Result<Self, MapError>
+	where
+		Word: Sub<Output = Word> + Ord,
+	{
+		let [k1, k2, k3, k4] = centers;
+		let gap = KeyMap {
+			k1,
+			k2,
+			k3,
+			k4,
+			margin: zero(),
+			margin_high: zero(),
+			noise_floor: zero(),
+		}
+		.min_gap();
+		Self::from_centers(centers, permille_of(gap, 400))
+	}
+
+	/// Finds the key for a voltage in millivolts, rather than a raw ADC reading.
+	///
+	/// The map is in ADC readings, so this relies on `k4` being the max reading of the ADC
+	/// as it is for predefined maps, which corresponds to `vref_mv`.
+	/// The voltage is converted to a reading of `mv / vref_mv * k4` and classified as usual.
+	/// The inverse of [`reading_to_millivolts`].
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// // Measured by another subsystem, on a 5V keypad.
+	/// assert_eq!(MAP_10BIT.key_from_millivolts(3000, 5000), Some(Key::K2));
+	/// assert_eq!(MAP_10BIT.key_from_millivolts(5000, 5000), Some(Key::K4));
+	/// ```
+	pub fn key_from_millivolts(&self, mv: u32, vref_mv: u32) -> Option<Key>
+	where
+		Word: Add<Output = Word> + Sub<Output = Word> + Ord,
+	{
+		if vref_mv == 0 {
+			return None;
+		}
+		let max = self.k4.into();
+		let reading = (mv as i64 * max / vref_mv as i64).min(max);
+		// Between 0 and `k4`, so it always fits in `Word`.
+		let reading = Word::try_from(reading).unwrap_or(self.k4);
+		self.key_from_reading(reading)
+	}
+
+	/// Sets the absolute margin on both sides to `pct` percent of `max`, the max reading of the ADC.
+	///
+	/// Unlike [`map_from_max!`][crate::map_from_max] which takes a factor such as `0.03`, this takes a percentage such as `3.0`.
+	/// So to restore the default margin, pass [`DEFAULT_MARGIN_FACTOR`] times `100.0`.
+	/// Not available with the `no-float` feature.
+	///
+	/// ```rust
+	/// use kc11b04::MAP_10BIT;
+	///
+	/// let mut map = MAP_10BIT;
+	/// map.set_margin_percent(5.0, 1023);
+	/// assert_eq!(map.margin, 51);
+	/// ```
+	#[cfg(not(feature = "no-float"))]
+	pub fn set_margin_percent(&mut self, pct: f32, max: Word) {
+		let margin = (max.into() as f32 * pct / 100.0) as i64;
+		// Margins that don't fit in `Word`, such as negative ones for unsigned words, become `0`.
+		self.set_margin(Word::try_from(margin).unwrap_or_else(|_| zero()));
+	}
+
+	/// The factor of `max` that a reading is, `val / max`, to compare against [`K1_F`], [`K2_F`] and [`K3_F`].
+	///
+	/// Useful while calibrating, to check the hardware divides the voltage the way this crate expects.
+	/// Not available with the `no-float` feature.
+	///
+	/// ```rust
+	/// use kc11b04::KeyMap;
+	///
+	/// // Reading 404 while holding K1 on a 10bit ADC, close to `K1_F`.
+	/// let factor = KeyMap::reading_to_factor(404u16, 1023);
+	/// assert!((factor - kc11b04::mapping::K1_F).abs() < 0.01);
+	/// ```
+	#[cfg(not(feature = "no-float"))]
+	pub fn reading_to_factor(val: Word, max: Word) -> f32 {
+		val.into() as f32 / max.into() as f32
+	}
 }
 
-#[test]
-fn read_10bit_samples() {
-	// Use `i16` as it's less commonly used in examples.
-	let map = crate::map_from_max!(i16, 1023, 0.03);
-	assert_eq!(
-		(
+impl<Word> KeyMap<Word>
+where
+	Word: Copy + Add<Output = Word> + Sub<Output = Word> + Ord + Into<i64> + TryFrom<i64>,
+{
+	/// Takes an ADC reading and finds whether it's in the expected range of a two key [`Combo`].
+	///
+	/// The expected values are derived from `k4` as the max reading, using the same margin as single keys.
+	/// Use this after [`KeyMap::key_from_reading`] returned [`None`], as the K2+K3 band lies close to K3.
+	///
+	/// See the [module documentation][crate::mapping#key-combinations] for which combinations can be recognized.
+	///
+	/// ```rust
+	/// use kc11b04::{Combo, MAP_10BIT};
+	///
+	/// assert_eq!(MAP_10BIT.key_from_reading(506), None);
+	/// assert_eq!(MAP_10BIT.combo_from_reading(506), Some(Combo::K1K2));
+	/// ```
+	pub fn combo_from_reading(&self, val: Word) -> Option<Combo> {
+		[
+			(Combo::K1K2, K1K2_PM),
+			(Combo::K1K3, K1K3_PM),
+			(Combo::K2K3, K2K3_PM),
+		]
+		.into_iter()
+		.find(|&(_, permille)| self.in_band(val, permille_of(self.k4, permille)))
+		.map(|(combo, _)| combo)
+	}
+
+	/// The inclusive range of readings accepted for `key`, as `(low, high)`. For example to draw a band chart.
+	///
+	/// The `high` bound is [`None`] when the band reaches the max reading of `Word`,
+	/// which is always the case for the open-ended K4 band.
+	/// The `low` bound is clamped to `0` if the margin reaches below what `Word` can represent.
+	///
+	/// ```rust
+	/// use kc11b04::{Key, MAP_10BIT};
+	///
+	/// let chart = Key::all().map(|key| MAP_10BIT.window(key));
+	/// let (k3, margin) = (MAP_10BIT.k3, MAP_10BIT.margin);
+	/// assert_eq!(chart[2], (k3 - margin, Some(k3 + margin)));
+	/// assert_eq!(chart[3], (MAP_10BIT.k4 - margin, None));
+	/// ```
+	pub fn window(&self, key: Key) -> (Word, Option<Word>) {
+		let expected = self.expected(key).into();
+		let low = Word::try_from(expected - self.margin.into()).unwrap_or_else(|_| zero());
+		let high = match key {
+			Key::K4 => None,
+			_ => Word::try_from(expected + self.margin_high.into()).ok(),
+		};
+		(low, high)
+	}
+}
+
+/// Converts an ADC reading into an estimated voltage in millivolts, using only integer math.
+///
+/// The reference voltage `vref_mv` should match what's on the `VCC` pin,
+/// which in turn should match the reference your ADC is configured to use (such as `AREF`).
+/// See the crate's [wiring notes][crate#wiring-notes]. `max` is the ADC's max reading.
+///
+/// Negative readings are reported as `0`.
+///
+/// ```rust
+/// use kc11b04::mapping::reading_to_millivolts;
+///
+/// // 10bit ADC with a 5V reference.
+/// assert_eq!(reading_to_millivolts(1023u16, 5000, 1023), 5000);
+/// ```
+pub fn reading_to_millivolts<Word>(val: Word, vref_mv: u32, max: Word) -> u32
+where
+	Word: Into<i64>,
+{
+	let (val, max): (i64, i64) = (val.into(), max.into());
+	if val <= 0 || max <= 0 {
+		return 0;
+	}
+	(val as u64 * vref_mv as u64 / max as u64) as u32
+}
+
+/// Maps any number of keys to their expected ADC readings.
+///
+/// A generalization of [`KeyMap`] for modules with a different number of keys using the same divider principle,
+/// such as the 8 key KC11B08. The expected values must be in increasing order, and like K4,
+/// the band of the last key is open-ended.
+///
+/// ```rust
+/// use kc11b04::mapping::KeyMapN;
+///
+/// let map = KeyMapN {
+/// 	expected: [128, 256, 384, 512, 640, 768, 896, 1023],
+/// 	margin: 30,
+/// 	margin_high: 30,
+/// };
+/// assert_eq!(map.key_from_reading(384), Some(2));
+/// ```
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyMapN<const N: usize, Word> {
+	/// The expected ADC readings for each key, before margins.
+	pub expected: [Word; N],
+
+	/// The absolute margin a reading may be below the above expected values.
+	pub margin: Word,
+
+	/// The absolute margin a reading may be above the expected values.
+	/// Not used for the last key, as its band is open-ended.
+	pub margin_high: Word,
+}
+
+impl<const N: usize, Word> KeyMapN<N, Word>
+where
+	Word: Copy + Add<Output = Word> + Sub<Output = Word> + Ord + TryFrom<i64>,
+{
+	/// Takes an ADC reading and finds the zero-based index of the key it's in the expected range of.
+	///
+	/// Will be [`None`] when no key is pressed, but also for some simultaneous key combinations.
+	pub fn key_from_reading(&self, val: Word) -> Option<u8> {
+		let last = N.checked_sub(1)?;
+		self.expected
+			.iter()
+			.position(|&expected| {
+				if val >= expected {
+					within_above(val, expected, self.margin_high)
+				} else {
+					within_below(val, expected, self.margin)
+				}
+			})
+			.or_else(|| (val >= self.expected[last]).then(|| last))
+			.map(|i| i as u8)
+	}
+}
+
+/// Manual impl, as `ufmt` only implements `uDebug` for arrays up to 32 items.
+#[cfg(feature = "ufmt-0-2")]
+impl<const N: usize, Word> ufmt::uDebug for KeyMapN<N, Word>
+where
+	Word: ufmt::uDebug,
+{
+	fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+	where
+		W: ufmt::uWrite + ?Sized,
+	{
+		f.debug_struct("KeyMapN")?
+			.field("expected", &&self.expected[..])?
+			.field("margin", &self.margin)?
+			.field("margin_high", &self.margin_high)?
+			.finish()
+	}
+}
+
+impl<Word> From<KeyMap<Word>> for KeyMapN<4, Word> {
+	fn from(map: KeyMap<Word>) -> Self {
+		KeyMapN {
+			expected: [map.k1, map.k2, map.k3, map.k4],
+			margin: map.margin,
+			margin_high: map.margin_high,
+		}
+	}
+}
+
+/// The value `0` in `Word`, which has no zero constant of its own.
+pub(crate) fn zero<Word>() -> Word
+where
+	Word: TryFrom<i64>,
+{
+	// Every integer type can represent `0`.
+	match Word::try_from(0) {
+		Ok(val) => val,
+		Err(_) => unreachable!(),
+	}
+}
+
+/// Takes `permille` (up to `1000`) of `max`, multiplying as `i128` so it won't overflow, even for `i64` words.
+pub(crate) fn permille_of<Word>(max: Word, permille: u16) -> Word
+where
+	Word: Into<i64> + TryFrom<i64>,
+{
+	// The result is between 0 and max, so it always fits in `Word`.
+	match Word::try_from((max.into() as i128 * permille as i128 / 1000) as i64) {
+		Ok(val) => val,
+		Err(_) => unreachable!(),
+	}
+}
+
+impl KeyMap<u16> {
+	/// Creates a [`KeyMap`] based on the max reading of the ADC and a margin factor, like [`map_from_max!`][crate::map_from_max].
+	///
+	/// Floating point math isn't allowed in a `const fn` on the crate's MSRV, so this is a runtime function.
+	/// For a constant map based on a named constant, use the macro instead.
+	///
+	/// ```rust
+	/// use kc11b04::{KeyMap, map_from_max};
+	///
+	/// const MAX: u16 = (1 << 10) - 1;
+	///
+	/// // Calculated at compile time.
+	/// const MAP: KeyMap<u16> = map_from_max!(u16, MAX, 0.03);
+	///
+	/// // Calculated at runtime.
+	/// let map = KeyMap::from_max(MAX, 0.03);
+	/// # assert_eq!((map.k1, map.k2, map.k3, map.k4, map.margin), (MAP.k1, MAP.k2, MAP.k3, MAP.k4, MAP.margin));
+	/// ```
+	#[cfg(not(feature = "no-float"))]
+	pub fn from_max(max: u16, margin: f32) -> Self {
+		crate::map_from_max!(u16, max, margin)
+	}
+
+	/// Creates a [`KeyMap`] based on the max reading of the ADC, using only integer math.
+	///
+	/// The expected values and margin are given in permille (1/1000th) of `max`.
+	/// Use [`K1_PM`], [`K2_PM`] and [`K3_PM`] for the module's regular factors.
+	///
+	/// Because of the coarser factors, the expected values may be off by one compared to [`map_from_max!`][crate::map_from_max].
+	///
+	/// ```rust
+	/// use kc11b04::KeyMap;
+	/// use kc11b04::mapping::{K1_PM, K2_PM, K3_PM};
+	///
+	/// /// 10bit map with a 3% margin, without any floating point math.
+	/// const MAP: KeyMap<u16> = KeyMap::from_max_permille(1023, K1_PM, K2_PM, K3_PM, 30);
+	/// ```
+	pub const fn from_max_permille(max: u16, k1: u16, k2: u16, k3: u16, margin: u16) -> Self {
+		const fn scale(max: u16, permille: u16) -> u16 {
+			(max as u32 * permille as u32 / 1000) as u16
+		}
+		KeyMap {
+			k1: scale(max, k1),
+			k2: scale(max, k2),
+			k3: scale(max, k3),
+			k4: max,
+			margin: scale(max, margin),
+			margin_high: scale(max, margin),
+			noise_floor: scale(max, k1).saturating_sub(scale(max, margin)),
+		}
+	}
+
+	/// Creates the [`KeyMap`] for an ADC with the given bit depth, with the default 3% margin.
+	///
+	/// The max reading is `(1 << BITS) - 1`, and `BITS` must fit in `u16`.
+	/// Other depths fail to compile.
+	///
+	/// ```rust
+	/// use kc11b04::KeyMap;
+	///
+	/// const MAP_13BIT: KeyMap<u16> = KeyMap::for_bits::<13>();
+	/// assert_eq!(MAP_13BIT.k4, 8191);
+	/// ```
+	///
+	/// ```compile_fail
+	/// use kc11b04::KeyMap;
+	///
+	/// const MAP_17BIT: KeyMap<u16> = KeyMap::for_bits::<17>();
+	/// ```
+	pub const fn for_bits<const BITS: u32>() -> Self {
+		Bits::<BITS>::MAP
+	}
+}
+
+/// Defines the predefined maps, using only integer math with the `no-float` feature.
+#[cfg(not(feature = "no-float"))]
+macro_rules! predefined_map {
+	($word:ident, $max:expr) => {
+		$crate::map_from_max!($word, $max)
+	};
+}
+
+/// Defines the predefined maps, using only integer math with the `no-float` feature.
+#[cfg(feature = "no-float")]
+macro_rules! predefined_map {
+	($word:ident, $max:expr) => {
+		$crate::map_from_max_permille!($word, $max)
+	};
+}
+
+pub(crate) use predefined_map;
+
+/// Holds the map for a bit depth as an associated constant,
+/// so the depth is checked and its floating point math done at compile time.
+struct Bits<const BITS: u32>;
+
+impl<const BITS: u32> Bits<BITS> {
+	const MAP: KeyMap<u16> = {
+		assert!(
+			BITS >= 1 && BITS <= 16,
+			"bit depth must be between 1 and 16 for u16 readings"
+		);
+		predefined_map!(u16, ((1u32 << BITS) - 1) as u16)
+	};
+}
+
+/// Problems found with a [`KeyMap`] by [`KeyMap::validate`].
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapError {
+	/// The expected value of the `upper` key isn't greater than that of the `lower` key.
+	NonMonotonic {
+		/// The key that should have the smaller expected value.
+		lower: Key,
+		/// The key that should have the greater expected value.
+		upper: Key,
+	},
+	/// The bands of two adjacent keys overlap, because the margins are too large for the gap between them.
+	Overlap {
+		/// The key with the smaller expected value.
+		lower: Key,
+		/// The key with the greater expected value.
+		upper: Key,
+	},
+	/// The margin below K1 is at least K1's expected value, so an idle keypad reading `0` would match K1.
+	MarginTooLarge,
+}
+
+/// Describes the problem, such as `bands of K1 and K2 overlap`.
+impl core::fmt::Display for MapError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			MapError::NonMonotonic { lower, upper } => {
+				write!(
+					f,
+					"expected value of {} isn't greater than {}",
+					upper, lower
+				)
+			}
+			MapError::Overlap { lower, upper } => {
+				write!(f, "bands of {} and {} overlap", lower, upper)
+			}
+			MapError::MarginTooLarge => f.write_str("margin below K1 reaches 0"),
+		}
+	}
+}
+
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+impl std::error::Error for MapError {}
+
+/// No key matches a reading, from [`KeyMap::try_key_from_reading`]. Holds the reading, for diagnostics.
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoKey<Word>(pub Word);
+
+/// Describes the problem, such as `no key matches reading 350`.
+impl<Word: core::fmt::Display> core::fmt::Display for NoKey<Word> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "no key matches reading {}", self.0)
+	}
+}
+
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+impl<Word: core::fmt::Debug + core::fmt::Display> std::error::Error for NoKey<Word> {}
+
+/// The absolute difference between `val` and `expected`.
+///
+/// Only ever subtracts the smaller value from the larger one,
+/// so it won't over- or underflow even when `expected` is near the limits of `Word`.
+pub(crate) fn distance<Word>(val: Word, expected: Word) -> Word
+where
+	Word: Copy + Sub<Output = Word> + Ord,
+{
+	if val >= expected {
+		val - expected
+	} else {
+		expected - val
+	}
+}
+
+/// Whether `val`, at or above `expected`, is at most `margin` away from it. Same as `val - expected <= margin`.
+///
+/// For signed words the subtraction could overflow when `expected` is negative, such as in an invalid map.
+/// Instead this compares `val` against the upper edge of the band, which can't overflow for a negative `expected`.
+pub(crate) fn within_above<Word>(val: Word, expected: Word, margin: Word) -> bool
+where
+	Word: Copy + Add<Output = Word> + Sub<Output = Word> + Ord + TryFrom<i64>,
+{
+	if expected >= zero() {
+		val - expected <= margin
+	} else {
+		val <= expected + margin
+	}
+}
+
+/// Whether `val`, below `expected`, is at most `margin` away from it. Same as `expected - val <= margin`.
+///
+/// For signed words the subtraction could overflow on readings far below zero, such as `i16::MIN`.
+/// Instead this compares `val` against the lower edge of the band, without any intermediate value
+/// leaving the range between `val` and `margin`.
+pub(crate) fn within_below<Word>(val: Word, expected: Word, margin: Word) -> bool
+where
+	Word: Copy + Add<Output = Word> + Sub<Output = Word> + Ord + TryFrom<i64>,
+{
+	if margin <= expected {
+		val >= expected - margin
+	} else if expected < zero() {
+		// Both are negative, with `val < expected`, so the difference fits.
+		expected - val <= margin
+	} else {
+		// Same as `val + margin - expected >= 0`, where `val < expected < margin`.
+		val + (margin - expected) >= zero()
+	}
+}
+
+/// Pull-down resistor value 100K ohms.
+#[cfg(not(feature = "no-float"))]
+const R_DOWN: f32 = 100_000.0;
+
+#[cfg(not(feature = "no-float"))]
+macro_rules! make_factor {
+	($r1:literal, $r_rest:literal, $r_down:ident) => {{
+		let r2 = 1.0 / ((1.0 / $r_rest) + (1.0 / $r_down));
+		r2 / ($r1 + r2)
+	}};
+}
+
+/// Relative factor for K1 button, `~39.5%` of the ADC's max reading.
+///
+/// See the module documentation [`kc11b04::mapping`][crate::mapping] for details.
+#[cfg(not(feature = "no-float"))]
+pub const K1_F: f32 = make_factor!(3000.0, 2000.0, R_DOWN);
+
+/// Relative factor for K2 button, `~59.3%` of the ADC's max reading.
+///
+/// See the module documentation [`kc11b04::mapping`][crate::mapping] for details.
+#[cfg(not(feature = "no-float"))]
+pub const K2_F: f32 = make_factor!(2000.0, 3000.0, R_DOWN);
+
+/// Relative factor for K3 button, `~79.4%` of the ADC's max reading.
+///
+/// See the module documentation [`kc11b04::mapping`][crate::mapping] for details.
+#[cfg(not(feature = "no-float"))]
+pub const K3_F: f32 = make_factor!(1000.0, 4000.0, R_DOWN);
+
+/// The default margin as a fraction of the ADC's max reading, not a percentage: `0.03` being 3%.
+///
+/// Used by [`map_from_max!`][crate::map_from_max] when the margin is omitted, and by the predefined maps.
+/// To pass it to [`KeyMap::set_margin_percent`], which takes a percentage, multiply it by `100.0`.
+#[cfg(not(feature = "no-float"))]
+pub const DEFAULT_MARGIN_FACTOR: f32 = 0.03;
+
+/// Relative factor for K1 button in permille, `395` being [`K1_F`] rounded.
+pub const K1_PM: u16 = 395;
+
+/// Relative factor for K2 button in permille, `593` being [`K2_F`] rounded.
+pub const K2_PM: u16 = 593;
+
+/// Relative factor for K3 button in permille, `794` being [`K3_F`] rounded.
+pub const K3_PM: u16 = 794;
+
+/// Same as [`K1_PM`], the relative factor for K1 button in permille.
+pub const K1_PERMILLE: u16 = K1_PM;
+
+/// Same as [`K2_PM`], the relative factor for K2 button in permille.
+pub const K2_PERMILLE: u16 = K2_PM;
+
+/// Same as [`K3_PM`], the relative factor for K3 button in permille.
+pub const K3_PERMILLE: u16 = K3_PM;
+
+/// Relative factor for K1 and K2 pressed together in permille, `~49.5%` of the ADC's max reading.
+///
+/// See the module documentation [`kc11b04::mapping`][crate::mapping#key-combinations] for details.
+pub const K1K2_PM: u16 = 495;
+
+/// Relative factor for K1 and K3 pressed together in permille, `~66.2%` of the ADC's max reading.
+///
+/// See the module documentation [`kc11b04::mapping`][crate::mapping#key-combinations] for details.
+pub const K1K3_PM: u16 = 662;
+
+/// Relative factor for K2 and K3 pressed together in permille, `~74.4%` of the ADC's max reading.
+///
+/// See the module documentation [`kc11b04::mapping`][crate::mapping#key-combinations] for details.
+pub const K2K3_PM: u16 = 744;
+
+/// Defines a [`KeyMap`] based on the max reading of the ADC and optional margin factor.
+///
+/// The margin defaults to [`DEFAULT_MARGIN_FACTOR`], `0.03` (3% of the max parameter), if omitted.
+///
+/// Both can be literals or constant expressions, such as a named constant.
+///
+/// ## Integer type
+///
+/// The macro and [`KeyMap`] take a `Word` generic parameter to support a variety of ADCs.
+///
+/// Keep in mind though this macro always maps the positive range, even when signed integers are used.
+/// As negative voltages are out-of-spec for the KC11B04 module.
+///
+/// ```rust
+/// # use kc11b04::{KeyMap, map_from_max};
+/// /// Signed 11bit map. Meaning 10 bits of resolution in the positive voltage range,
+/// /// so the max `1023` is the same as the 10 bit map.
+/// const SIGNED_11BIT: KeyMap<i16> = map_from_max!(i16, 1023);
+///
+/// /// 24bit map needs a u32 to fit.
+/// const UNSIGNED_24BIT: KeyMap<u32> = map_from_max!(u32, 0xFFFFFF);
+/// ```
+///
+/// ## Performance note
+///
+/// This macro will use floating point math. It's strongly recommended to
+/// **declare the result as a constant**, to avoid this FP math at runtime.
+///
+/// ```rust
+/// # use kc11b04::{KeyMap, map_from_max};
+/// /// 10bit map, but with 15% margin.
+/// const CUSTOM_MAP: KeyMap<u16> = map_from_max!(u16, 1023, 0.15);
+/// ```
+#[cfg(not(feature = "no-float"))]
+#[macro_export]
+macro_rules! map_from_max {
+	($word:ident, $max:expr) => {
+		$crate::map_from_max!($word, $max, $crate::mapping::DEFAULT_MARGIN_FACTOR)
+	};
+	($word:ident, $max:expr, $margin:expr) => {
+		$crate::mapping::KeyMap {
+			k1: ($max as f32 * $crate::mapping::K1_F) as $word,
+			k2: ($max as f32 * $crate::mapping::K2_F) as $word,
+			k3: ($max as f32 * $crate::mapping::K3_F) as $word,
+			k4: $max,
+			margin: ($max as f32 * $margin) as $word,
+			margin_high: ($max as f32 * $margin) as $word,
+			noise_floor: (($max as f32 * $crate::mapping::K1_F) as $word)
+				.saturating_sub(($max as f32 * $margin) as $word),
+		}
+	};
+}
+
+/// Defines a [`KeyMap`] based on the max reading of the ADC and optional margin in permille, using only integer math.
+///
+/// Like [`map_from_max!`][crate::map_from_max] this supports any `Word` type, but without floating point math.
+/// The margin defaults to `30` (3% of the max parameter) if omitted.
+/// Because of the coarser factors, the expected values may be off by one compared to [`map_from_max!`][crate::map_from_max].
+///
+/// ```rust
+/// # use kc11b04::{KeyMap, map_from_max_permille};
+/// /// 24bit map with a 15% margin.
+/// const CUSTOM_MAP: KeyMap<u32> = map_from_max_permille!(u32, 0xFFFFFF, 150);
+/// ```
+#[macro_export]
+macro_rules! map_from_max_permille {
+	($word:ident, $max:expr) => {
+		$crate::map_from_max_permille!($word, $max, 30)
+	};
+	($word:ident, $max:expr, $margin:expr) => {
+		$crate::mapping::KeyMap {
+			k1: ($max as i64 * $crate::mapping::K1_PM as i64 / 1000) as $word,
+			k2: ($max as i64 * $crate::mapping::K2_PM as i64 / 1000) as $word,
+			k3: ($max as i64 * $crate::mapping::K3_PM as i64 / 1000) as $word,
+			k4: $max,
+			margin: ($max as i64 * $margin as i64 / 1000) as $word,
+			margin_high: ($max as i64 * $margin as i64 / 1000) as $word,
+			noise_floor: ($max as i64 * $crate::mapping::K1_PM as i64 / 1000
+				- $max as i64 * $margin as i64 / 1000) as $word,
+		}
+	};
+}
+
+#[cfg(not(feature = "no-float"))]
+#[test]
+fn read_10bit_samples() {
+	// Use `i16` as it's less commonly used in examples.
+	let map = crate::map_from_max!(i16, 1023, 0.03);
+	assert_eq!(
+		(
 			map.key_from_reading(0),
 			map.key_from_reading(300),
 			map.key_from_reading(400),
@@ -268,3 +1569,1156 @@ fn read_10bit_samples() {
 		)
 	);
 }
+
+#[test]
+fn margin_larger_than_k1() {
+	let map: KeyMap<u8> = KeyMap {
+		k1: 10,
+		k2: 100,
+		k3: 150,
+		k4: 200,
+		margin: 30,
+		margin_high: 30,
+		noise_floor: 0,
+	};
+	assert_eq!(
+		(
+			map.key_from_reading(0),
+			map.key_from_reading(5),
+			map.key_from_reading(40),
+			map.key_from_reading(41),
+		),
+		(Some(Key::K1), Some(Key::K1), Some(Key::K1), None)
+	);
+}
+
+#[test]
+fn k3_margin_beyond_word_max() {
+	// `k3 + margin` would be 260, which doesn't fit `u8`.
+	let map: KeyMap<u8> = KeyMap {
+		k1: 100,
+		k2: 170,
+		k3: 240,
+		k4: 255,
+		margin: 20,
+		margin_high: 20,
+		noise_floor: 0,
+	};
+	assert_eq!(
+		(
+			map.key_from_reading(221),
+			map.key_from_reading(240),
+			map.key_from_reading(250),
+			map.key_from_reading(255),
+		),
+		(Some(Key::K3), Some(Key::K3), Some(Key::K3), Some(Key::K3))
+	);
+}
+
+#[test]
+fn validate_maps() {
+	let manufacturer = KeyMap {
+		k1: 404,
+		k2: 607,
+		k3: 812,
+		k4: 1023,
+		margin: 30,
+		margin_high: 30,
+		noise_floor: 0,
+	};
+	let swapped = KeyMap {
+		k2: 812,
+		k3: 607,
+		..manufacturer
+	};
+	let k3_k4_overlap = KeyMap {
+		k3: 1000,
+		..manufacturer
+	};
+	assert_eq!(
+		(
+			manufacturer.validate(),
+			swapped.validate(),
+			k3_k4_overlap.validate(),
+		),
+		(
+			Ok(()),
+			Err(MapError::NonMonotonic {
+				lower: Key::K2,
+				upper: Key::K3
+			}),
+			Err(MapError::Overlap {
+				lower: Key::K3,
+				upper: Key::K4
+			}),
+		)
+	);
+}
+
+#[test]
+fn read_wide_and_signed_samples() {
+	use crate::{MAP_12BIT_SIGNED, MAP_24BIT};
+
+	assert_eq!(
+		(
+			MAP_24BIT.key_from_reading(0),
+			MAP_24BIT.key_from_reading(6_600_000),
+			MAP_24BIT.key_from_reading(8_400_000),
+			MAP_24BIT.key_from_reading(10_000_000),
+			MAP_24BIT.key_from_reading(11_600_000),
+			MAP_24BIT.key_from_reading(13_300_000),
+			MAP_24BIT.key_from_reading(0xFFFFFF)
+		),
+		(
+			None,
+			Some(Key::K1),
+			None,
+			Some(Key::K2),
+			None,
+			Some(Key::K3),
+			Some(Key::K4)
+		)
+	);
+	assert_eq!(
+		(
+			MAP_12BIT_SIGNED.key_from_reading(0),
+			MAP_12BIT_SIGNED.key_from_reading(800),
+			MAP_12BIT_SIGNED.key_from_reading(1000),
+			MAP_12BIT_SIGNED.key_from_reading(1200),
+			MAP_12BIT_SIGNED.key_from_reading(1400),
+			MAP_12BIT_SIGNED.key_from_reading(1620),
+			MAP_12BIT_SIGNED.key_from_reading(2047)
+		),
+		(
+			None,
+			Some(Key::K1),
+			None,
+			Some(Key::K2),
+			None,
+			Some(Key::K3),
+			Some(Key::K4)
+		)
+	);
+}
+
+#[test]
+fn scored_distance() {
+	use crate::MAP_10BIT;
+
+	let k3 = MAP_10BIT.k3;
+	let margin = MAP_10BIT.margin;
+	assert_eq!(
+		(
+			MAP_10BIT.key_from_reading_scored(k3),
+			MAP_10BIT.key_from_reading_scored(k3 + 10),
+			MAP_10BIT.key_from_reading_scored(k3 - margin),
+			MAP_10BIT.key_from_reading_scored(k3 - (margin + 1)),
+		),
+		(
+			Some((Key::K3, 0)),
+			Some((Key::K3, 10)),
+			Some((Key::K3, margin)),
+			None,
+		)
+	);
+}
+
+#[test]
+fn expected_per_key() {
+	let map = KeyMap {
+		k1: 1,
+		k2: 2,
+		k3: 3,
+		k4: 4,
+		margin: 0,
+		margin_high: 0,
+		noise_floor: 0,
+	};
+	assert_eq!(Key::all().map(|key| map.expected(key)), [1, 2, 3, 4]);
+}
+
+#[cfg(not(feature = "no-float"))]
+#[test]
+fn from_max_matches_macro() {
+	use crate::MAP_10BIT;
+
+	let map = KeyMap::from_max(1023, 0.03);
+	assert_eq!(map, MAP_10BIT);
+}
+
+#[test]
+fn permille_matches_float_map() {
+	use crate::MAP_10BIT;
+
+	let map = KeyMap::from_max_permille(1023, K1_PM, K2_PM, K3_PM, 30);
+	for key in Key::all() {
+		let (int, float) = (map.expected(key), MAP_10BIT.expected(key));
+		assert!(int.max(float) - int.min(float) <= 1, "{:?}", key);
+	}
+	assert_eq!(map.margin, MAP_10BIT.margin);
+}
+
+#[cfg(feature = "serde-1")]
+#[test]
+fn serde_derives() {
+	use serde_test::{assert_tokens, Token};
+
+	assert_tokens(
+		&Key::K3,
+		&[Token::UnitVariant {
+			name: "Key",
+			variant: "K3",
+		}],
+	);
+
+	let map: KeyMap<i16> = KeyMap {
+		k1: 404,
+		k2: 607,
+		k3: 812,
+		k4: 1023,
+		margin: 30,
+		margin_high: 20,
+		noise_floor: -5,
+	};
+	assert_tokens(
+		&map,
+		&[
+			Token::Struct {
+				name: "KeyMap",
+				len: 7,
+			},
+			Token::Str("k1"),
+			Token::I16(404),
+			Token::Str("k2"),
+			Token::I16(607),
+			Token::Str("k3"),
+			Token::I16(812),
+			Token::Str("k4"),
+			Token::I16(1023),
+			Token::Str("margin"),
+			Token::I16(30),
+			Token::Str("margin_high"),
+			Token::I16(20),
+			Token::Str("noise_floor"),
+			Token::I16(-5),
+			Token::StructEnd,
+		],
+	);
+}
+
+#[test]
+fn read_10bit_combos() {
+	use crate::MAP_10BIT;
+
+	// 10bit readings for 49.5%, 66.2% and 74.4%.
+	assert_eq!(
+		(
+			MAP_10BIT.combo_from_reading(506),
+			MAP_10BIT.combo_from_reading(677),
+			MAP_10BIT.combo_from_reading(761),
+			MAP_10BIT.combo_from_reading(0),
+			MAP_10BIT.combo_from_reading(MAP_10BIT.k2),
+		),
+		(
+			Some(Combo::K1K2),
+			Some(Combo::K1K3),
+			Some(Combo::K2K3),
+			None,
+			None,
+		)
+	);
+	assert_eq!(
+		(
+			MAP_10BIT.key_from_reading(506),
+			MAP_10BIT.key_from_reading(677),
+			MAP_10BIT.key_from_reading(761),
+		),
+		(None, None, None)
+	);
+}
+
+#[test]
+fn scaled_14bit_samples() {
+	let unsigned: KeyMap<u16> = KeyMap::scaled(16383);
+	let signed: KeyMap<i16> = KeyMap::scaled(8191);
+	assert_eq!(
+		(
+			unsigned.key_from_reading(0),
+			unsigned.key_from_reading(6471),
+			unsigned.key_from_reading(9715),
+			unsigned.key_from_reading(13008),
+			unsigned.key_from_reading(16383),
+			unsigned.key_from_reading(8000),
+		),
+		(
+			None,
+			Some(Key::K1),
+			Some(Key::K2),
+			Some(Key::K3),
+			Some(Key::K4),
+			None,
+		)
+	);
+	assert_eq!(
+		(
+			signed.key_from_reading(0),
+			signed.key_from_reading(3235),
+			signed.key_from_reading(4857),
+			signed.key_from_reading(6504),
+			signed.key_from_reading(8191),
+			signed.key_from_reading(4000),
+		),
+		(
+			None,
+			Some(Key::K1),
+			Some(Key::K2),
+			Some(Key::K3),
+			Some(Key::K4),
+			None,
+		)
+	);
+}
+
+#[test]
+fn widen_margin() {
+	use crate::MAP_10BIT;
+
+	let mut map = MAP_10BIT;
+	assert_eq!(map.key_from_reading(650), None);
+	map.set_margin(81);
+	assert_eq!(map.key_from_reading(650), Some(Key::K2));
+	assert_eq!(
+		MAP_10BIT.with_margin(81).key_from_reading(650),
+		Some(Key::K2)
+	);
+}
+
+#[test]
+fn read_8_key_samples() {
+	let map = KeyMapN {
+		expected: [128u16, 256, 384, 512, 640, 768, 896, 1023],
+		margin: 30,
+		margin_high: 30,
+	};
+	assert_eq!(
+		[0, 130, 250, 390, 500, 645, 770, 900, 1023, 192].map(|r| map.key_from_reading(r)),
+		[
+			None,
+			Some(0),
+			Some(1),
+			Some(2),
+			Some(3),
+			Some(4),
+			Some(5),
+			Some(6),
+			Some(7),
+			None
+		]
+	);
+}
+
+#[test]
+fn key_map_n_from_key_map() {
+	use crate::MAP_10BIT;
+
+	let map: KeyMapN<4, u16> = MAP_10BIT.into();
+	assert_eq!(
+		[0, 404, 606, 811, 1023].map(|r| map.key_from_reading(r)),
+		[0, 404, 606, 811, 1023].map(|r| MAP_10BIT.key_from_reading(r).map(Key::index))
+	);
+
+	// Asymmetric margins carry over, rather than widening both sides.
+	let asymmetric = MAP_10BIT.with_margins(10, 50);
+	let map = KeyMapN::from(asymmetric);
+	assert_eq!(
+		[380, 393, 394, 454, 455].map(|r| map.key_from_reading(r)),
+		[380, 393, 394, 454, 455].map(|r| asymmetric.key_from_reading(r).map(Key::index))
+	);
+	assert_eq!(map.key_from_reading(380), None);
+}
+
+#[test]
+fn millivolts_from_readings() {
+	assert_eq!(
+		(
+			reading_to_millivolts(0u16, 5000, 1023),
+			reading_to_millivolts(512u16, 5000, 1023),
+			reading_to_millivolts(1023u16, 5000, 1023),
+			reading_to_millivolts(2048u16, 3300, 4095),
+			reading_to_millivolts(4095u16, 3300, 4095),
+			reading_to_millivolts(0xFFFFFFu32, 5000, 0xFFFFFF),
+			reading_to_millivolts(-5i16, 5000, 2047),
+		),
+		(0, 2502, 5000, 1650, 3300, 5000, 0)
+	);
+}
+
+#[test]
+fn read_11bit_samples() {
+	let map = crate::MAP_11BIT;
+	assert_eq!(
+		[0, 600, 809, 1000, 1213, 1400, 1624, 1800, 2047].map(|r| map.key_from_reading(r)),
+		[
+			None,
+			None,
+			Some(Key::K1),
+			None,
+			Some(Key::K2),
+			None,
+			Some(Key::K3),
+			None,
+			Some(Key::K4)
+		]
+	);
+}
+
+#[test]
+fn read_14bit_samples() {
+	let map = crate::MAP_14BIT;
+	assert_eq!(
+		[0, 5000, 6475, 8000, 9713, 11500, 13002, 14500, 16383].map(|r| map.key_from_reading(r)),
+		[
+			None,
+			None,
+			Some(Key::K1),
+			None,
+			Some(Key::K2),
+			None,
+			Some(Key::K3),
+			None,
+			Some(Key::K4)
+		]
+	);
+}
+
+#[test]
+fn asymmetric_margins() {
+	let narrow_high = KeyMap {
+		k1: 404u16,
+		k2: 607,
+		k3: 812,
+		k4: 1023,
+		margin: 30,
+		margin_high: 10,
+		noise_floor: 0,
+	};
+	let wide_high = KeyMap {
+		margin: 10,
+		margin_high: 50,
+		..narrow_high
+	};
+	assert_eq!(
+		[580, 600, 640].map(|r| narrow_high.key_from_reading(r)),
+		[Some(Key::K2), Some(Key::K2), None]
+	);
+	assert_eq!(
+		[580, 600, 640].map(|r| wide_high.key_from_reading(r)),
+		[None, Some(Key::K2), Some(Key::K2)]
+	);
+	assert_eq!(wide_high.validate(), Ok(()));
+}
+
+#[test]
+fn ambiguous_between_bands() {
+	let map = crate::MAP_10BIT;
+	assert_eq!(
+		[0, map.noise_floor - 1, 500, map.k2, 710, 1023].map(|r| map.key_state_from_reading(r)),
+		[
+			KeyState::None,
+			KeyState::None,
+			KeyState::Ambiguous,
+			KeyState::Pressed(Key::K2),
+			KeyState::Ambiguous,
+			KeyState::Pressed(Key::K4)
+		]
+	);
+
+	let quiet = KeyMap {
+		noise_floor: 600,
+		..map
+	};
+	assert_eq!(quiet.key_state_from_reading(500), KeyState::None);
+}
+
+#[test]
+fn k4_first_classifies_the_same() {
+	let map = crate::MAP_10BIT;
+	for val in 0..=1023 {
+		assert_eq!(
+			map.key_from_reading_k4_first(val),
+			map.key_from_reading(val),
+			"reading {}",
+			val
+		);
+	}
+}
+
+#[test]
+fn zero_margin_matches_exact_reading() {
+	let map = crate::MAP_10BIT.with_margin(0);
+	assert_eq!(
+		[map.k1, map.k2, map.k2 - 1, map.k2 + 1, map.k3, map.k4].map(|r| map.key_from_reading(r)),
+		[
+			Some(Key::K1),
+			Some(Key::K2),
+			None,
+			None,
+			Some(Key::K3),
+			Some(Key::K4)
+		]
+	);
+}
+
+#[test]
+fn inclusive_band_edges() {
+	let map = crate::MAP_10BIT;
+	let (low, high) = (map.margin, map.margin_high);
+	for key in [Key::K1, Key::K2, Key::K3] {
+		let expected = map.expected(key);
+		assert_eq!(
+			[
+				expected - low - 1,
+				expected - low,
+				expected + high,
+				expected + high + 1
+			]
+			.map(|r| map.key_from_reading(r)),
+			[None, Some(key), Some(key), None],
+			"{}",
+			key
+		);
+	}
+	assert_eq!(
+		[map.k4 - low - 1, map.k4 - low, map.k4].map(|r| map.key_from_reading(r)),
+		[None, Some(Key::K4), Some(Key::K4)]
+	);
+}
+
+#[test]
+fn touching_bands_overlap() {
+	let map = KeyMap {
+		k1: 100u16,
+		k2: 160,
+		k3: 220,
+		k4: 280,
+		margin: 30,
+		margin_high: 30,
+		noise_floor: 0,
+	};
+	assert_eq!(
+		map.validate(),
+		Err(MapError::Overlap {
+			lower: Key::K1,
+			upper: Key::K2
+		})
+	);
+	assert_eq!(map.with_margin(29).validate(), Ok(()));
+}
+
+#[test]
+fn classify_10bit_slice() {
+	let map = crate::MAP_10BIT;
+	let readings = [
+		0, 100, 200, 300, 400, 450, 500, 550, 600, 650, 700, 750, 800, 850, 900, 950, 1000, 1023,
+	];
+	let mut keys = [None; 18];
+	map.classify_slice(&readings, &mut keys);
+	assert_eq!(keys, readings.map(|r| map.key_from_reading(r)));
+
+	// Extra outputs are left untouched.
+	let mut keys = [Some(Key::K2); 3];
+	map.classify_slice(&readings[..2], &mut keys);
+	assert_eq!(keys, [None, None, Some(Key::K2)]);
+}
+
+#[test]
+fn events_from_readings() {
+	let readings = [0, 404, 0, 404, 404, 404, 607, 607, 0, 0, 1023];
+	let map = crate::MAP_10BIT;
+
+	let mut events = [None; 5];
+	for (event, out) in map.events(readings, 1).zip(&mut events) {
+		*out = Some(event);
+	}
+	assert_eq!(
+		events,
+		[
+			Some(Event::Pressed(Key::K1)),
+			Some(Event::Released(Key::K1)),
+			Some(Event::Pressed(Key::K1)),
+			Some(Event::Changed {
+				from: Some(Key::K1),
+				to: Some(Key::K2)
+			}),
+			Some(Event::Released(Key::K2)),
+		]
+	);
+	assert_eq!(map.events(readings, 1).count(), 6);
+
+	let mut events = [None; 4];
+	for (event, out) in map.events(readings, 2).zip(&mut events) {
+		*out = Some(event);
+	}
+	assert_eq!(
+		events,
+		[
+			Some(Event::Pressed(Key::K1)),
+			Some(Event::Changed {
+				from: Some(Key::K1),
+				to: Some(Key::K2)
+			}),
+			Some(Event::Released(Key::K2)),
+			None,
+		]
+	);
+}
+
+#[test]
+fn maps_for_bits() {
+	use crate::{MAP_10BIT, MAP_12BIT, MAP_14BIT};
+
+	assert_eq!(KeyMap::for_bits::<10>(), MAP_10BIT);
+	assert_eq!(KeyMap::for_bits::<12>(), MAP_12BIT);
+	assert_eq!(KeyMap::for_bits::<14>(), MAP_14BIT);
+}
+
+#[test]
+fn negative_signed_readings() {
+	use crate::{MAP_12BIT_SIGNED, MAP_16BIT_SIGNED};
+
+	assert_eq!(
+		[-5, -1, i16::MIN, 0].map(|r| MAP_12BIT_SIGNED.key_from_reading(r)),
+		[None; 4]
+	);
+	assert_eq!(
+		[-5, -1, i16::MIN, 0].map(|r| MAP_16BIT_SIGNED.key_from_reading(r)),
+		[None; 4]
+	);
+	assert_eq!(MAP_16BIT_SIGNED.key_state_from_reading(-5), KeyState::None);
+
+	// With a margin larger than K1, K1's band reaches below zero.
+	let map = KeyMap {
+		k1: 10i16,
+		k2: 100,
+		k3: 150,
+		k4: 200,
+		margin: 30,
+		margin_high: 30,
+		noise_floor: 0,
+	};
+	assert_eq!(
+		[-20, -21, i16::MIN].map(|r| map.key_from_reading(r)),
+		[Some(Key::K1), None, None]
+	);
+	let map_n = KeyMapN::from(map);
+	assert_eq!(
+		[-20, -21, i16::MIN].map(|r| map_n.key_from_reading(r)),
+		[Some(0), None, None]
+	);
+}
+
+#[test]
+fn closest_key_in_gaps() {
+	let map = crate::MAP_10BIT;
+	assert_eq!(
+		[0, 450, 550, 650, 750, 850, 1023].map(|r| map.closest_key(r)),
+		[
+			Key::K1,
+			Key::K1,
+			Key::K2,
+			Key::K2,
+			Key::K3,
+			Key::K3,
+			Key::K4
+		]
+	);
+	assert_eq!(
+		[i16::MIN, -5, i16::MAX].map(|r| crate::MAP_12BIT_SIGNED.closest_key(r)),
+		[Key::K1, Key::K1, Key::K4]
+	);
+}
+
+#[test]
+fn permille_macro_matches_const_fn() {
+	let map: KeyMap<u16> = crate::map_from_max_permille!(u16, 1023, 150);
+	assert_eq!(
+		map,
+		KeyMap::from_max_permille(1023, K1_PM, K2_PM, K3_PM, 150)
+	);
+
+	let map: KeyMap<u32> = crate::map_from_max_permille!(u32, 0xFFFFFF);
+	assert_eq!(
+		[0, 6_600_000, 8_400_000, 10_000_000, 13_300_000, 0xFFFFFF]
+			.map(|r| map.key_from_reading(r)),
+		[
+			None,
+			Some(Key::K1),
+			None,
+			Some(Key::K2),
+			Some(Key::K3),
+			Some(Key::K4)
+		]
+	);
+}
+
+#[cfg(not(feature = "no-float"))]
+#[test]
+fn permille_factors_are_rounded() {
+	assert_eq!(
+		[K1_F, K2_F, K3_F].map(|f| (f * 1000.0 + 0.5) as u16),
+		[K1_PERMILLE, K2_PERMILLE, K3_PERMILLE]
+	);
+}
+
+#[test]
+fn key_windows() {
+	let map = crate::MAP_8BIT;
+	assert_eq!(map.window(Key::K4), (map.k4 - map.margin, None));
+
+	// `k3 + margin` would be 260, and `k1 - margin` below 0.
+	let map: KeyMap<u8> = KeyMap {
+		k1: 10,
+		k2: 170,
+		k3: 240,
+		k4: 255,
+		margin: 20,
+		margin_high: 20,
+		noise_floor: 0,
+	};
+	assert_eq!(
+		Key::all().map(|key| map.window(key)),
+		[(0, Some(30)), (150, Some(190)), (220, None), (235, None)]
+	);
+}
+
+#[cfg(not(feature = "no-float"))]
+#[test]
+fn margin_from_percent() {
+	let mut map = crate::MAP_10BIT.with_margin(0);
+	map.set_margin_percent(3.0, 1023);
+	assert_eq!((map.margin, map.margin_high), (30, 30));
+	assert_eq!(map.margin, crate::MAP_10BIT.margin);
+}
+
+#[cfg(not(feature = "no-float"))]
+#[test]
+fn factor_of_k1_reading() {
+	let map = crate::MAP_12BIT;
+	let factor = KeyMap::reading_to_factor(map.k1, map.k4);
+	assert!((factor - K1_F).abs() < 1.0 / 4095.0);
+	assert_eq!(KeyMap::reading_to_factor(map.k4, map.k4), 1.0);
+}
+
+#[test]
+fn read_8bit_boundaries() {
+	let map = crate::MAP_8BIT;
+	let k3_high = map.k3 + map.margin_high;
+	let k4_low = map.k4 - map.margin;
+	assert_eq!(
+		[k3_high, k3_high + 1, k4_low - 1, k4_low, 255].map(|r| map.key_from_reading(r)),
+		[Some(Key::K3), None, None, Some(Key::K4), Some(Key::K4)]
+	);
+
+	// Margins larger than the expected values shouldn't overflow `u8` either.
+	let map = map.with_margin(210);
+	assert_eq!(
+		[0, 100, 255].map(|r| map.key_from_reading(r)),
+		[Some(Key::K1), Some(Key::K1), Some(Key::K1)]
+	);
+	assert_eq!(map.window(Key::K1), (0, None));
+}
+
+#[test]
+fn read_9bit_samples() {
+	let map = KeyMap::for_bits::<9>();
+	assert_eq!(
+		[0, 100, 202, 250, 303, 355, 406, 460, 511].map(|r| map.key_from_reading(r)),
+		[
+			None,
+			None,
+			Some(Key::K1),
+			None,
+			Some(Key::K2),
+			None,
+			Some(Key::K3),
+			None,
+			Some(Key::K4)
+		]
+	);
+}
+
+#[test]
+fn read_13bit_samples() {
+	let readings = [0, 2000, 3235, 4050, 4857, 5700, 6503, 7400, 8191];
+	let keys = [
+		None,
+		None,
+		Some(Key::K1),
+		None,
+		Some(Key::K2),
+		None,
+		Some(Key::K3),
+		None,
+		Some(Key::K4),
+	];
+
+	let map = KeyMap::for_bits::<13>();
+	assert_eq!(readings.map(|r| map.key_from_reading(r)), keys);
+	#[cfg(not(feature = "no-float"))]
+	{
+		let map = crate::map_from_max!(u16, 8191);
+		assert_eq!(readings.map(|r| map.key_from_reading(r)), keys);
+	}
+}
+
+#[test]
+fn macro_maps_equal_constants() {
+	use crate::{MAP_12BIT, MAP_12BIT_SIGNED};
+
+	#[cfg(not(feature = "no-float"))]
+	let (map, signed): (KeyMap<u16>, KeyMap<i16>) = (
+		crate::map_from_max!(u16, 4095),
+		crate::map_from_max!(i16, 2047),
+	);
+	#[cfg(feature = "no-float")]
+	let (map, signed): (KeyMap<u16>, KeyMap<i16>) = (
+		crate::map_from_max_permille!(u16, 4095),
+		crate::map_from_max_permille!(i16, 2047),
+	);
+	assert_eq!(map, MAP_12BIT);
+	assert_eq!(signed, MAP_12BIT_SIGNED);
+	assert_ne!(map, MAP_12BIT.with_margin(0));
+}
+
+#[test]
+fn copy_map_as_backup() {
+	fn backup<Word: Clone>(map: &KeyMap<Word>) -> KeyMap<Word> {
+		map.clone()
+	}
+
+	let mut map = crate::MAP_10BIT;
+	let copy = map;
+	let clone = backup(&map);
+	map.set_margin(60);
+	assert_ne!(map, copy);
+	assert_eq!((copy, clone), (crate::MAP_10BIT, crate::MAP_10BIT));
+}
+
+#[test]
+fn idle_below_noise_floor() {
+	let map = KeyMap {
+		noise_floor: 100,
+		..crate::MAP_10BIT
+	};
+	assert_eq!(
+		[0, 50, 99, 100, 350, 404].map(|r| map.key_state_from_reading(r)),
+		[
+			KeyState::None,
+			KeyState::None,
+			KeyState::None,
+			KeyState::Ambiguous,
+			KeyState::Ambiguous,
+			KeyState::Pressed(Key::K1)
+		]
+	);
+	// Only the key state tells idle and unrecognized readings apart.
+	assert_eq!([50, 350].map(|r| map.key_from_reading(r)), [None, None]);
+}
+
+#[test]
+fn default_noise_floor_at_k1_band() {
+	// Every reading below the K1 band is idle, regardless of rounding.
+	for map in [
+		crate::MAP_10BIT,
+		crate::MAP_11BIT,
+		crate::MAP_12BIT,
+		KeyMap::scaled(1023),
+	] {
+		let edge = map.k1 - map.margin;
+		assert_eq!(map.noise_floor, edge);
+		assert_eq!(
+			[edge - 1, edge].map(|r| map.key_state_from_reading(r)),
+			[KeyState::None, KeyState::Pressed(Key::K1)]
+		);
+	}
+}
+
+#[test]
+fn margin_reaching_idle() {
+	let map = KeyMap {
+		k1: 100,
+		k2: 400,
+		k3: 700,
+		k4: 1000,
+		margin: 100,
+		margin_high: 100,
+		noise_floor: 0,
+	};
+	assert_eq!(map.validate(), Err(MapError::MarginTooLarge));
+	assert_eq!(map.with_margin(99).validate(), Ok(()));
+}
+
+#[test]
+fn k4_above_max() {
+	let map = crate::MAP_10BIT;
+	assert_eq!(
+		[1023, 1024, 1100, u16::MAX].map(|r| map.key_from_reading(r)),
+		[Some(Key::K4); 4]
+	);
+	assert_eq!(map.key_from_reading_k4_first(1100), Some(Key::K4));
+	assert_eq!(map.key_state_from_reading(1100), KeyState::Pressed(Key::K4));
+}
+
+#[test]
+fn nudge_single_key() {
+	let map = crate::MAP_10BIT;
+	let nudged = map.with_key(Key::K2, map.k2 + 10);
+	assert_eq!(
+		nudged,
+		KeyMap {
+			k2: map.k2 + 10,
+			..map
+		}
+	);
+
+	let (low, high) = (map.k2 - map.margin, map.k2 + map.margin_high);
+	assert_eq!(
+		[low, low + 10, high, high + 10].map(|r| nudged.key_from_reading(r)),
+		[None, Some(Key::K2), Some(Key::K2), Some(Key::K2)]
+	);
+	assert_eq!(nudged.key_from_reading(high + 11), None);
+}
+
+#[test]
+fn k3_margin_reaching_k4() {
+	let map = KeyMap {
+		k1: 200,
+		k2: 400,
+		k3: 600,
+		k4: 700,
+		margin: 10,
+		margin_high: 95,
+		noise_floor: 190,
+	};
+	assert_eq!(
+		map.validate(),
+		Err(MapError::Overlap {
+			lower: Key::K3,
+			upper: Key::K4
+		})
+	);
+	assert_eq!(map.key_from_reading(map.k4 - map.margin), Some(Key::K3));
+	assert_eq!(
+		KeyMap {
+			margin_high: 89,
+			..map
+		}
+		.validate(),
+		Ok(())
+	);
+}
+
+#[test]
+fn no_key_in_gap() {
+	let map = crate::MAP_10BIT;
+	assert_eq!(
+		[0, 350, 607, 1023].map(|r| map.try_key_from_reading(r)),
+		[Err(NoKey(0)), Err(NoKey(350)), Ok(Key::K2), Ok(Key::K4)]
+	);
+}
+
+#[cfg(not(feature = "no-float"))]
+#[test]
+fn default_margin_constant() {
+	let map: KeyMap<u16> = crate::map_from_max!(u16, 4095);
+	let margin = (4095.0 * DEFAULT_MARGIN_FACTOR) as u16;
+	assert_eq!((map.margin, map.margin_high), (margin, margin));
+	assert_eq!(crate::MAP_12BIT.margin, margin);
+}
+
+#[cfg(not(feature = "no-float"))]
+#[test]
+fn default_margin_as_percent() {
+	let mut map = crate::MAP_12BIT.with_margin(300);
+	map.set_margin_percent(DEFAULT_MARGIN_FACTOR * 100.0, 4095);
+	assert_eq!(map, crate::MAP_12BIT);
+}
+
+#[test]
+fn shares_map_in_static() {
+	fn assert_sync<T: Sync>(_: &T) {}
+	static MAP: KeyMap<u16> = crate::MAP_10BIT;
+	assert_sync(&MAP);
+	assert_eq!(MAP.key_from_reading(607), Some(Key::K2));
+}
+
+#[test]
+fn builds_from_centers() {
+	let centers = [120u16, 333, 501, 960];
+	let map = KeyMap::from_centers(centers, 40).unwrap();
+	assert_eq!(
+		centers.map(|r| map.key_from_reading(r)),
+		[Some(Key::K1), Some(Key::K2), Some(Key::K3), Some(Key::K4)]
+	);
+	assert_eq!(map.key_from_reading(79), None);
+	assert_eq!(
+		KeyMap::from_centers([120u16, 333, 501, 960], 90),
+		Err(MapError::Overlap {
+			lower: Key::K2,
+			upper: Key::K3
+		})
+	);
+	assert_eq!(
+		KeyMap::from_centers([120u16, 333, 300, 960], 10),
+		Err(MapError::NonMonotonic {
+			lower: Key::K2,
+			upper: Key::K3
+		})
+	);
+}
+
+#[test]
+fn min_gap_of_10bit() {
+	let map = crate::MAP_10BIT;
+	assert_eq!(map.min_gap(), map.k2 - map.k1);
+	assert_eq!(map.with_key(Key::K3, 1000).min_gap(), 23);
+}
+
+#[test]
+fn builds_from_centers_with_auto_margin() {
+	let centers = [120u16, 333, 501, 960];
+	let map = KeyMap::from_centers_auto(centers).unwrap();
+	assert_eq!(map.margin, 67);
+	assert_eq!(map.validate(), Ok(()));
+	assert_eq!(
+		centers.map(|r| map.key_from_reading(r)),
+		[Some(Key::K1), Some(Key::K2), Some(Key::K3), Some(Key::K4)]
+	);
+	assert_eq!(
+		KeyMap::from_centers_auto([20i16, 333, 501, 960]),
+		Err(MapError::MarginTooLarge)
+	);
+}
+
+#[test]
+fn keys_from_millivolts() {
+	let map: KeyMap<u16> = KeyMap::scaled(1023);
+	assert_eq!(
+		[0, 2000, 3000, 4000, 5000, 6000].map(|mv| map.key_from_millivolts(mv, 5000)),
+		[
+			None,
+			Some(Key::K1),
+			Some(Key::K2),
+			Some(Key::K3),
+			Some(Key::K4),
+			Some(Key::K4)
+		]
+	);
+	assert_eq!(map.key_from_millivolts(3000, 0), None);
+}
+
+#[test]
+fn builds_from_sorted_centers() {
+	let sorted = KeyMap::from_centers([120u16, 333, 501, 960], 40);
+	assert_eq!(
+		KeyMap::from_centers_sorted([960u16, 120, 501, 333], 40),
+		sorted
+	);
+	assert_eq!(sorted.map(|map| map.validate()), Ok(Ok(())));
+	assert_eq!(
+		KeyMap::from_centers_sorted([120u16, 333, 333, 960], 40),
+		Err(MapError::NonMonotonic {
+			lower: Key::K2,
+			upper: Key::K3
+		})
+	);
+}
+
+#[test]
+fn reduces_mostly_k2_buffer() {
+	let map = crate::MAP_10BIT;
+	let buffer = [0, map.k2, map.k2 + 5, 0, map.k2 - 5, map.k2, 0, map.k2];
+	assert_eq!(map.reduce_buffer(&buffer), Some(Key::K2));
+	assert_eq!(map.reduce_buffer(&[0, 0, map.k2]), None);
+	assert_eq!(map.reduce_buffer(&[map.k3, map.k2]), Some(Key::K2));
+	assert_eq!(map.reduce_buffer(&[]), None);
+}
+
+#[test]
+fn shifts_signed_idle_baseline() {
+	let map = KeyMap::<i16>::scaled(2047);
+	let shifted = map.with_idle_baseline(-10).unwrap();
+	assert_eq!(shifted.validate(), Ok(()));
+	assert_eq!(
+		[-25, -10, 0].map(|r| shifted.key_state_from_reading(r)),
+		[KeyState::None; 3]
+	);
+	assert_eq!(
+		[map.k1 - 10, map.k1 - 20, map.k2 - 10, 2047].map(|r| shifted.key_from_reading(r)),
+		[Some(Key::K1), Some(Key::K1), Some(Key::K2), Some(Key::K4)]
+	);
+	// Shifting past the limits of `Word` fails, rather than wrapping.
+	assert_eq!(crate::MAP_16BIT_SIGNED.with_idle_baseline(10), None);
+	assert_eq!(crate::MAP_16BIT.with_idle_baseline(1), None);
+	// As does shifting K1 below its margin, which would make an invalid map.
+	assert_eq!(crate::MAP_12BIT_SIGNED.with_idle_baseline(-1000), None);
+	assert_eq!(crate::MAP_12BIT_SIGNED.with_idle_baseline(-760), None);
+	assert_eq!(
+		crate::MAP_10BIT
+			.with_idle_baseline(1)
+			.map(|map| (map.k4, map.margin)),
+		Some((1024, crate::MAP_10BIT.margin))
+	);
+}
+
+#[test]
+fn negative_expected_values_dont_overflow() {
+	// Invalid, but classifying with it shouldn't panic either.
+	let map: KeyMap<i16> = KeyMap {
+		k1: -191,
+		k2: 213,
+		k3: 624,
+		k4: 1047,
+		margin: 61,
+		margin_high: 61,
+		noise_floor: -252,
+	};
+	assert_eq!(map.validate(), Err(MapError::MarginTooLarge));
+	assert_eq!(
+		[i16::MIN, -200, i16::MAX].map(|r| map.key_from_reading(r)),
+		[None, Some(Key::K1), Some(Key::K4)]
+	);
+	let map = KeyMap { k4: 32000, ..map };
+	assert_eq!(map.key_from_reading(i16::MAX), Some(Key::K4));
+	let map = KeyMap { k1: -32000, ..map };
+	assert_eq!(
+		[i16::MIN, i16::MAX, 0].map(|r| map.key_from_reading(r)),
+		[None, Some(Key::K4), None]
+	);
+}
+
+#[test]
+fn scales_widest_words() {
+	let map = KeyMap::<i64>::scaled(i64::MAX);
+	assert_eq!(map.k4, i64::MAX);
+	assert_eq!(map.k1, (i64::MAX as i128 * K1_PM as i128 / 1000) as i64);
+	assert_eq!(map.validate(), Ok(()));
+	assert_eq!(map.key_from_reading(i64::MAX), Some(Key::K4));
+	assert_eq!(map.key_from_reading(map.k2), Some(Key::K2));
+}
+
+#[test]
+fn fits_word_types() {
+	let map: KeyMap<u16> = KeyMap::scaled(255);
+	assert!(map.fits::<u8>());
+	assert!(!crate::MAP_16BIT.fits::<u8>());
+	assert!(!crate::MAP_16BIT.fits::<i16>());
+	// Shifted far enough for a lowered noise floor to go negative.
+	assert!(!crate::MAP_12BIT_SIGNED
+		.with_noise_floor(0)
+		.with_idle_baseline(-700)
+		.unwrap()
+		.fits::<u16>());
+	assert!(crate::MAP_12BIT_SIGNED.fits::<u16>());
+	assert!(crate::MAP_24BIT.fits::<i32>());
+}
@@ -0,0 +1,25 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Re-exports the items needed for typical use of the driver, to import with a single `use`.
+//!
+//! ```rust
+//! use kc11b04::prelude::*;
+//! # use embedded_hal_mock::adc::*;
+//! #
+//! # let mut adc = Mock::new(&[Transaction::read(0, 1023)]);
+//! # let analog_pin = MockChan0;
+//!
+//! let mut keypad = KC11B04::new(analog_pin, MAP_10BIT);
+//! assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K4)));
+//! ```
+
+#[cfg(not(feature = "no-float"))]
+pub use crate::map_from_max;
+pub use crate::map_from_max_permille;
+pub use crate::{Key, KeyMap, KC11B04};
+pub use crate::{
+	MAP_10BIT, MAP_11BIT, MAP_12BIT, MAP_12BIT_SIGNED, MAP_14BIT, MAP_16BIT, MAP_16BIT_SIGNED,
+	MAP_24BIT, MAP_8BIT,
+};
@@ -0,0 +1,153 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use embedded_hal::adc::{Channel, OneShot};
+
+use crate::{driver::NbError, Classifier, Key, KeyMap, KC11B04};
+
+/// Wraps a [`KC11B04`] driver to report the majority vote of the last `N` key states.
+/// Constructed with [`RollingDebounce::new`].
+///
+/// Unlike [`Debounced`][crate::Debounced] the readings don't need to agree several times in a row,
+/// so isolated misreads are smoothed out even when polling at a high rate.
+/// The history is kept in a stack array and starts out as `N` readings without a key.
+/// On a tie, the previously reported state is kept if it's among the most common ones.
+pub struct RollingDebounce<Pin, ADC, Word, const N: usize, Map = KeyMap<Word>> {
+	keypad: KC11B04<Pin, ADC, Word, Map>,
+	history: [Option<Key>; N],
+	next: usize,
+	stable: Option<Key>,
+}
+
+impl<Pin, ADC, Word, const N: usize, Map> RollingDebounce<Pin, ADC, Word, N, Map>
+where
+	Pin: Channel<ADC>,
+	Word: Copy,
+	Map: Classifier<Word>,
+{
+	/// Wrap a [`KC11B04`] driver, voting over the last `N` reads.
+	///
+	/// ```rust
+	/// # use embedded_hal_mock::adc::*;
+	/// # use kc11b04::{Key, KC11B04, RollingDebounce, MAP_10BIT};
+	/// #
+	/// # let mut adc = Mock::new(&[
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 1023),
+	/// # 	Transaction::read(0, 0),
+	/// # 	Transaction::read(0, 1023),
+	/// # ]);
+	/// # let analog_pin = MockChan0;
+	/// #
+	/// // Report the most common key state out of the last 3 reads.
+	/// let mut keypad: RollingDebounce<_, _, _, 3> = RollingDebounce::new(KC11B04::new(analog_pin, MAP_10BIT));
+	///
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(None));
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K4)));
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K4)));
+	/// assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K4)));
+	/// ```
+	pub fn new(keypad: KC11B04<Pin, ADC, Word, Map>) -> Self {
+		Self {
+			keypad,
+			history: [None; N],
+			next: 0,
+			stable: None,
+		}
+	}
+
+	/// Takes an ADC reading and returns the most common key state of the last `N` reads.
+	///
+	/// Will be [`None`] when no key is pressed, but also for some simultaneous key combinations.
+	pub fn key_state<Adc>(
+		&mut self,
+		adc: &mut Adc,
+	) -> Result<Option<Key>, NbError<Adc, ADC, Word, Pin>>
+	where
+		Adc: OneShot<ADC, Word, Pin>,
+	{
+		let key = self.keypad.key_state(adc)?;
+		if N == 0 {
+			return Ok(key);
+		}
+		self.history[self.next] = key;
+		self.next = (self.next + 1) % N;
+
+		// One slot for no key, followed by K1 to K4.
+		let slot = |key: Option<Key>| key.map_or(0, |k| k.index() as usize + 1);
+		let mut votes = [0usize; Key::COUNT + 1];
+		for key in self.history {
+			votes[slot(key)] += 1;
+		}
+		let mut winner = self.stable;
+		for candidate in [
+			None,
+			Some(Key::K1),
+			Some(Key::K2),
+			Some(Key::K3),
+			Some(Key::K4),
+		] {
+			if votes[slot(candidate)] > votes[slot(winner)] {
+				winner = candidate;
+			}
+		}
+		self.stable = winner;
+		Ok(winner)
+	}
+
+	/// Releases the wrapped [`KC11B04`] driver.
+	pub fn into_inner(self) -> KC11B04<Pin, ADC, Word, Map> {
+		self.keypad
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{Key, RollingDebounce, KC11B04, MAP_10BIT};
+	use embedded_hal::adc::Channel;
+	use embedded_hal_mock::adc::{Mock, MockChan0, Transaction};
+
+	#[test]
+	fn outvotes_misreads() {
+		use MockChan0 as PIN;
+
+		// Once full, the window holds 2 wrong readings out of 5.
+		let readings = [600, 600, 600, 800, 600, 0, 600, 600];
+		let expected = readings.map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad: RollingDebounce<_, _, _, 5> =
+			RollingDebounce::new(KC11B04::new(PIN, MAP_10BIT));
+
+		let states = readings.map(|_| keypad.key_state(&mut adc).unwrap());
+
+		assert_eq!(
+			states,
+			[
+				None,
+				None,
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K2),
+				Some(Key::K2),
+			]
+		);
+	}
+
+	#[test]
+	fn keeps_state_on_tie() {
+		use MockChan0 as PIN;
+
+		let readings = [600, 600, 0];
+		let expected = readings.map(|r| Transaction::read(PIN::channel(), r));
+		let mut adc = Mock::new(&expected);
+		let mut keypad: RollingDebounce<_, _, _, 2> =
+			RollingDebounce::new(KC11B04::new(PIN, MAP_10BIT));
+
+		let states = readings.map(|_| keypad.key_state(&mut adc).unwrap());
+
+		assert_eq!(states, [None, Some(Key::K2), Some(Key::K2)]);
+	}
+}
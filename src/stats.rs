@@ -0,0 +1,144 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+/// Tracks the min, max and average of raw readings, to help pick calibration bands in the field.
+/// Constructed with [`ReadingStats::new`].
+///
+/// Feed it every raw reading (for example from [`KC11B04::read_raw`][crate::KC11B04::read_raw])
+/// while pressing a single key a few times, then inspect the results.
+/// Uses only integer math, and [`reset`][ReadingStats::reset] to start over for the next key.
+///
+/// ```rust
+/// use kc11b04::ReadingStats;
+///
+/// let mut stats = ReadingStats::new();
+/// for reading in [401u16, 409, 404, 398] {
+/// 	stats.update(reading);
+/// }
+///
+/// assert_eq!((stats.min(), stats.max(), stats.average()), (Some(398), Some(409), Some(403)));
+/// ```
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadingStats<Word> {
+	min: Option<Word>,
+	max: Option<Word>,
+	/// Wide enough for [`u32::MAX`] readings of any `Word`.
+	sum: i128,
+	count: u32,
+}
+
+impl<Word> ReadingStats<Word>
+where
+	Word: Copy + Ord + Into<i64> + TryFrom<i64>,
+{
+	/// Create a [`ReadingStats`] without any readings.
+	pub fn new() -> Self {
+		Self {
+			min: None,
+			max: None,
+			sum: 0,
+			count: 0,
+		}
+	}
+
+	/// Adds a raw reading to the stats.
+	pub fn update(&mut self, val: Word) {
+		self.min = Some(self.min.map_or(val, |min| min.min(val)));
+		self.max = Some(self.max.map_or(val, |max| max.max(val)));
+		// Once the count saturates, stop adding to the sum so the average stays consistent.
+		if self.count < u32::MAX {
+			self.sum += val.into() as i128;
+			self.count += 1;
+		}
+	}
+
+	/// Forgets all readings, for example before moving on to the next key.
+	pub fn reset(&mut self) {
+		*self = Self::new();
+	}
+
+	/// The smallest reading so far.
+	pub fn min(&self) -> Option<Word> {
+		self.min
+	}
+
+	/// The largest reading so far.
+	pub fn max(&self) -> Option<Word> {
+		self.max
+	}
+
+	/// The average of all readings since the last [`reset`][ReadingStats::reset], rounded towards `0`.
+	pub fn average(&self) -> Option<Word> {
+		if self.count == 0 {
+			return None;
+		}
+		// The average of `Word` values always fits in `Word`.
+		Word::try_from((self.sum / self.count as i128) as i64).ok()
+	}
+
+	/// The number of readings so far, saturating at [`u32::MAX`].
+	pub fn count(&self) -> u32 {
+		self.count
+	}
+}
+
+impl<Word> Default for ReadingStats<Word>
+where
+	Word: Copy + Ord + Into<i64> + TryFrom<i64>,
+{
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::ReadingStats;
+
+	#[test]
+	fn tracks_extremes() {
+		let mut stats = ReadingStats::new();
+		assert_eq!(
+			(stats.min(), stats.max(), stats.average()),
+			(None, None, None)
+		);
+
+		for reading in [-3i16, 120, 7, -40, 95] {
+			stats.update(reading);
+		}
+		assert_eq!(
+			(stats.min(), stats.max(), stats.average(), stats.count()),
+			(Some(-40), Some(120), Some(35), 5)
+		);
+
+		stats.reset();
+		stats.update(12);
+		assert_eq!(
+			(stats.min(), stats.max(), stats.average()),
+			(Some(12), Some(12), Some(12))
+		);
+	}
+
+	#[test]
+	fn averages_whole_session() {
+		let mut stats = ReadingStats::new();
+		for reading in [404u16, 404, 607, 607] {
+			stats.update(reading);
+		}
+		assert_eq!(stats.average(), Some(505));
+
+		let mut stats = ReadingStats::new();
+		stats.update(u32::MAX);
+		stats.update(0);
+		assert_eq!(stats.average(), Some(u32::MAX / 2));
+
+		let mut stats = ReadingStats::new();
+		stats.update(i64::MAX);
+		stats.update(i64::MAX - 2);
+		stats.update(i64::MIN);
+		assert_eq!(stats.average(), Some(i64::MAX / 3 - 1));
+	}
+}
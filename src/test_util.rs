@@ -0,0 +1,100 @@
+// Copyright 2023-2023 Robin van Boven
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Helpers for testing code that uses the keypad, without depending on `embedded-hal-mock`.
+//!
+//! Requires the `test-util` feature.
+//!
+//! ```rust
+//! use kc11b04::test_util::{TestAdc, TestPin};
+//! use kc11b04::{Key, KC11B04, MAP_10BIT};
+//!
+//! let mut adc = TestAdc::new(&[0, 404, 1023]);
+//! let mut keypad = KC11B04::new(TestPin, MAP_10BIT);
+//!
+//! assert_eq!(keypad.key_state(&mut adc), Ok(None));
+//! assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K1)));
+//! assert_eq!(keypad.key_state(&mut adc), Ok(Some(Key::K4)));
+//! ```
+
+use embedded_hal::adc::{Channel, OneShot};
+
+/// An ADC that returns canned readings in order, for any [`Channel`] of it.
+#[derive(Debug)]
+pub struct TestAdc<'a, Word> {
+	readings: &'a [Word],
+}
+
+/// Reading a [`TestAdc`] after all of its readings were used.
+#[cfg_attr(feature = "defmt-0-3", derive(defmt::Format))]
+#[cfg_attr(feature = "ufmt-0-2", derive(ufmt::derive::uDebug))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exhausted;
+
+/// A pin to use with [`TestAdc`], as channel `0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestPin;
+
+impl<'a, Word> TestAdc<'a, Word> {
+	/// Creates an ADC that returns each of `readings` once, then fails with [`Exhausted`].
+	pub fn new(readings: &'a [Word]) -> Self {
+		Self { readings }
+	}
+
+	/// The readings that haven't been used yet.
+	pub fn remaining(&self) -> &'a [Word] {
+		self.readings
+	}
+}
+
+impl<'a, Word> Channel<TestAdc<'a, Word>> for TestPin {
+	type ID = u8;
+
+	fn channel() -> u8 {
+		0
+	}
+}
+
+impl<'a, Word, Pin> OneShot<TestAdc<'a, Word>, Word, Pin> for TestAdc<'a, Word>
+where
+	Word: Copy,
+	Pin: Channel<TestAdc<'a, Word>>,
+{
+	type Error = Exhausted;
+
+	fn read(&mut self, _pin: &mut Pin) -> nb::Result<Word, Exhausted> {
+		let (first, rest) = self
+			.readings
+			.split_first()
+			.ok_or(nb::Error::Other(Exhausted))?;
+		self.readings = rest;
+		Ok(*first)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Exhausted, TestAdc, TestPin};
+	use crate::{Event, Key, KC11B04, MAP_10BIT};
+
+	#[test]
+	fn returns_readings_in_order() {
+		let mut adc = TestAdc::new(&[0, 607, 812]);
+		let mut keypad = KC11B04::new(TestPin, MAP_10BIT);
+
+		assert_eq!(keypad.poll_event(&mut adc), Ok(None));
+		assert_eq!(adc.remaining(), &[607, 812]);
+		assert_eq!(
+			[(); 3].map(|_| keypad.poll_event(&mut adc)),
+			[
+				Ok(Some(Event::Pressed(Key::K2))),
+				Ok(Some(Event::Changed {
+					from: Some(Key::K2),
+					to: Some(Key::K3)
+				})),
+				Err(nb::Error::Other(Exhausted)),
+			]
+		);
+	}
+}